@@ -1,7 +1,6 @@
-use gemini_client_api::gemini::ask::Gemini;
-use gemini_client_api::gemini::types::request::Tool;
+use gemini_client_api::gemini::ask::{AgentConfig, Gemini};
 use gemini_client_api::gemini::types::sessions::Session;
-use gemini_client_api::gemini::utils::{GeminiSchema, execute_function_calls, gemini_function};
+use gemini_client_api::gemini::utils::{GeminiSchema, ToolRegistry, gemini_function};
 use std::env;
 use std::error::Error;
 
@@ -31,49 +30,43 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let mut session = Session::new(10);
     let api_key = env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
 
-    // 1. Initialize Gemini and register tools
-    let ai =
-        Gemini::new(api_key, "gemini-2.5-flash", None).set_tools(vec![Tool::FunctionDeclarations(
-            vec![
-                add_numbers::gemini_schema(),
-                get_temperature::gemini_schema(),
-            ],
-        )]);
+    // 1. Register tools and initialize Gemini with them. The registry also asks the approver of
+    // functions marked `#[gemini_function(requires_approval)]`.
+    let mut registry = ToolRegistry::new();
+    registry
+        .register::<add_numbers>()
+        .register::<get_temperature>();
+    let ai = Gemini::new(api_key, "gemini-2.5-flash", None).set_tools(vec![registry.to_tool()]);
 
     let prompt = "What is 123.45 plus 678.9, and what's the weather like in London?";
     println!("User: {}\n", prompt);
 
-    // 2. Ask Gemini. It might reply with one or more function calls.
-    let mut response = ai.ask(session.ask(prompt)).await?;
-
-    // 3. Loop to handle potential multiple rounds of function calls
-    loop {
-        if response.get_chat().has_function_call() {
-            println!("Gemini requested function calls...");
-
-            // 4. Use the macro to execute all requested calls and update the session
-            let results = execute_function_calls!(session, add_numbers, get_temperature);
-
-            for (idx, res) in results.iter().enumerate() {
-                if let Some(r) = res {
-                    println!("  Call #{} result: {:?}", idx, r);
-                }
-            }
-
-            // 5. Send the results back to Gemini to get the final natural language response
-            response = ai.ask(&mut session).await?;
-        } else {
-            // No more function calls, show the final response
-            println!("\nGemini: {}", response.get_chat().get_text_no_think(""));
-            break;
-        }
+    // 2. Let the agent ask, run the requested function calls and send their results back
+    // until Gemini replies in natural language.
+    session.ask(prompt);
+    let run = ai
+        .run_agent(
+            &mut session,
+            &registry,
+            AgentConfig::default().set_max_iterations(5),
+        )
+        .await?;
+
+    for record in &run.function_calls {
+        println!("  {} result: {:?}", record.call.name(), record.result);
     }
+    println!(
+        "\nGemini: {}",
+        run.response.get_chat().get_text_no_think("")
+    );
 
     Ok(())
 }
 
 #[tokio::test]
 async fn handle_manually() {
+    use gemini_client_api::gemini::types::request::Tool;
+    use gemini_client_api::gemini::utils::execute_function_calls;
     let mut session = Session::new(10);
     let api_key = env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
 
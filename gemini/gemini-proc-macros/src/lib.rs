@@ -7,12 +7,28 @@ use syn::{
 /// Attribute macro to mark a function as callable by Gemini.
 ///
 /// This macro generates a schema for the function and an `execute` method to call it
-/// with JSON arguments.
+/// with JSON arguments. It also implements `GeminiFunction`, so that the function can be
+/// added to a `ToolRegistry`.
 ///
 /// # Requirements
 /// - Function arguments must be owned types that implement `GeminiSchema` (e.g., `String`, `i32`, `bool`).
 /// - References are not supported.
 /// - The function can be `async` and can return a `Result` (the `Ok` value must implement `Serialize`).
+/// - Arguments and the future of an `async` function must be `Send`, as the generated
+///   `GeminiFunction` implementation boxes the call. This holds even if the function is never
+///   added to a `ToolRegistry`.
+///
+/// # Approval
+/// `#[gemini_function(requires_approval)]` marks a function, like one sending email, to be
+/// run by a `ToolRegistry` only after its approver approves the call. `execute_function_calls!`
+/// fails to compile for such functions, and the generated `execute` skips approval.
+///
+/// # Context
+/// Parameters marked `#[context]` are left out of the schema and taken from the
+/// `ToolContext` passed to `execute_with_context` (or set on a `ToolRegistry`), by type. Use
+/// them for state like a database pool or the current user. Their types must be `Clone`, so
+/// wrap distinct values of the same type in newtypes. `execute_function_calls!` fails to
+/// compile for such functions, as it has no context to pass.
 ///
 /// # Example
 /// ```ignore
@@ -21,9 +37,29 @@ use syn::{
 /// fn get_weather(location: String) -> String {
 ///     format!("The weather in {} is sunny.", location)
 /// }
+///
+/// #[gemini_function]
+/// /// Returns the orders of the current user.
+/// async fn get_orders(#[context] db: Database, #[context] user: UserId, limit: i32) -> Vec<String> {
+///     db.orders(user, limit).await
+/// }
 /// ```
 #[proc_macro_attribute]
-pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn gemini_function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let requires_approval = if attr.is_empty() {
+        false
+    } else {
+        let attr = parse_macro_input!(attr as syn::Ident);
+        if attr != "requires_approval" {
+            return syn::Error::new_spanned(
+                &attr,
+                "unknown argument, only `requires_approval` is supported",
+            )
+            .to_compile_error()
+            .into();
+        }
+        true
+    };
     let mut input_fn = parse_macro_input!(item as ItemFn);
     let fn_name = &input_fn.sig.ident;
     let args_struct_name = syn::Ident::new(&format!("{}_args", fn_name), fn_name.span());
@@ -33,6 +69,9 @@ pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut required = Vec::new();
     let mut param_names = Vec::new();
     let mut param_types = Vec::new();
+    let mut context_names = Vec::new();
+    let mut context_types = Vec::new();
+    let mut call_args = Vec::new();
 
     for arg in input_fn.sig.inputs.iter_mut() {
         if let FnArg::Typed(pat_type) = arg {
@@ -51,8 +90,22 @@ pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
                     .into();
                 }
 
-                // Remove doc attributes from the function signature so it compiles
-                pat_type.attrs.retain(|attr| !attr.path().is_ident("doc"));
+                let is_context = pat_type
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("context"));
+                // Remove doc and context attributes from the function signature so it compiles
+                pat_type
+                    .attrs
+                    .retain(|attr| !attr.path().is_ident("doc") && !attr.path().is_ident("context"));
+
+                if is_context {
+                    call_args.push(quote! { #param_name });
+                    context_names.push(param_name);
+                    context_types.push(param_type);
+                    continue;
+                }
+                call_args.push(quote! { args.#param_name });
 
                 let is_optional = is_option(&param_type);
 
@@ -104,6 +157,7 @@ pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
             Ok(serde_json::json!(result))
         }
     };
+    let uses_context = !context_names.is_empty();
 
     let expanded = quote! {
         #input_fn
@@ -136,10 +190,23 @@ pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
         }
 
         impl #fn_name {
+            /// Runs the function with `args` given by the model. Skips approval even if the
+            /// function requires it, run it by a `ToolRegistry` for that.
             pub async fn execute(args: &serde_json::Value) -> Result<serde_json::Value, String> {
+                Self::execute_with_context(args, &gemini_client_api::gemini::utils::ToolContext::default()).await
+            }
+            /// Same as `execute` but with `#[context]` parameters taken from `context`. Skips
+            /// approval too.
+            pub async fn execute_with_context(args: &serde_json::Value, context: &gemini_client_api::gemini::utils::ToolContext) -> Result<serde_json::Value, String> {
                 use gemini_client_api::serde::Deserialize;
                 let args = #args_struct_name::deserialize(args).map_err(|e| e.to_string())?;
-                let result = #fn_name(#(args.#param_names),*) #call_await;
+                let _ = context;
+                #(
+                    let #context_names: #context_types = context.get::<#context_types>().ok_or_else(|| {
+                        format!("context of type `{}` was not provided", stringify!(#context_types))
+                    })?;
+                )*
+                let result = #fn_name(#(#call_args),*) #call_await;
                 #result_handling
             }
             pub fn parse_arguments(args: &serde_json::Value) -> Result<(#(#param_types,)*), serde_json::Error>
@@ -149,6 +216,16 @@ pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 Ok((#(args.#param_names,)*))
             }
         }
+
+        impl gemini_client_api::gemini::utils::GeminiFunction for #fn_name {
+            const NAME: &'static str = #fn_name_str;
+            const REQUIRES_APPROVAL: bool = #requires_approval;
+            const USES_CONTEXT: bool = #uses_context;
+            fn call(args: gemini_client_api::serde_json::Value, context: &gemini_client_api::gemini::utils::ToolContext) -> gemini_client_api::futures::future::BoxFuture<'static, Result<gemini_client_api::serde_json::Value, String>> {
+                let context = context.clone();
+                Box::pin(async move { #fn_name::execute_with_context(&args, &context).await })
+            }
+        }
     };
 
     expanded.into()
@@ -158,6 +235,7 @@ pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// # Usage
 /// `execute_function_calls_with_callback!(session, callback, function1, function2, ...)`
+/// `execute_function_calls_with_callback!(session, callback, timeout = duration, max_concurrency = n, function1, ...)`
 ///
 /// The `callback` should be a closure or function that takes `(String, Result<serde_json::Value, String>)`
 /// and returns `serde_json::Value`.
@@ -173,6 +251,9 @@ pub fn gemini_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// The `session` is automatically updated with the `FunctionResponse`.
 /// The `callback` is invoked with the result of the function execution (whether `Ok` or `Err`)
 /// and its return value is used to update the session.
+/// All calls run concurrently and without timeout by default. The optional `timeout` (a
+/// `std::time::Duration`) cancels calls running longer and gives them an error as result, and
+/// `max_concurrency` (a `usize`) limits the calls run at once. Either may be left out.
 #[proc_macro]
 pub fn execute_function_calls_with_callback(input: TokenStream) -> TokenStream {
     use syn::parse::{Parse, ParseStream};
@@ -183,6 +264,7 @@ pub fn execute_function_calls_with_callback(input: TokenStream) -> TokenStream {
         _comma1: Token![,],
         callback: Expr,
         _comma2: Token![,],
+        options: ExecuteOptions,
         functions: syn::punctuated::Punctuated<syn::Path, Token![,]>,
     }
 
@@ -193,13 +275,19 @@ pub fn execute_function_calls_with_callback(input: TokenStream) -> TokenStream {
                 _comma1: input.parse()?,
                 callback: input.parse()?,
                 _comma2: input.parse()?,
+                options: input.parse()?,
                 functions: input.parse_terminated(syn::Path::parse, Token![,])?,
             })
         }
     }
 
     let input = parse_macro_input!(input as ExecuteWithCallbackInput);
-    generate_execute_logic(&input.session, &input.callback, &input.functions)
+    generate_execute_logic(
+        &input.session,
+        &input.callback,
+        &input.options,
+        &input.functions,
+    )
 }
 
 /// Attribute macro to derive the `GeminiSchema` trait for a struct or enum.
@@ -384,6 +472,7 @@ fn has_reference(ty: &Type) -> bool {
 ///
 /// # Usage
 /// `execute_function_calls!(session, function1, function2, ...)`
+/// `execute_function_calls!(session, timeout = duration, max_concurrency = n, function1, ...)`
 ///
 /// # Returns
 /// A `Vec<Option<Result<serde_json::Value, String>>>` containing the results of each function call.
@@ -396,6 +485,10 @@ fn has_reference(ty: &Type) -> bool {
 /// The `session` is automatically updated with the `FunctionResponse` for successful calls.
 /// If a function call fails, the error is converted to a JSON object `{"Error": error_message}`
 /// and sent to the session as the function response.
+///
+/// All calls run concurrently and without timeout by default. The optional `timeout` (a
+/// `std::time::Duration`) cancels calls running longer and gives them an error as result, and
+/// `max_concurrency` (a `usize`) limits the calls run at once. Either may be left out.
 #[proc_macro]
 pub fn execute_function_calls(input: TokenStream) -> TokenStream {
     use syn::parse::{Parse, ParseStream};
@@ -404,6 +497,7 @@ pub fn execute_function_calls(input: TokenStream) -> TokenStream {
     struct ExecuteInput {
         session: Expr,
         _comma: Token![,],
+        options: ExecuteOptions,
         functions: syn::punctuated::Punctuated<syn::Path, Token![,]>,
     }
 
@@ -412,6 +506,7 @@ pub fn execute_function_calls(input: TokenStream) -> TokenStream {
             Ok(ExecuteInput {
                 session: input.parse()?,
                 _comma: input.parse()?,
+                options: input.parse()?,
                 functions: input.parse_terminated(syn::Path::parse, Token![,])?,
             })
         }
@@ -427,23 +522,93 @@ pub fn execute_function_calls(input: TokenStream) -> TokenStream {
         }
     };
 
-    generate_execute_logic(&input.session, &callback, &input.functions)
+    generate_execute_logic(&input.session, &callback, &input.options, &input.functions)
+}
+
+/// Optional `timeout = ..., max_concurrency = ...,` before the functions of the execute macros.
+#[derive(Default)]
+struct ExecuteOptions {
+    timeout: Option<syn::Expr>,
+    max_concurrency: Option<syn::Expr>,
+}
+
+impl syn::parse::Parse for ExecuteOptions {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut options = ExecuteOptions::default();
+        while input.peek(syn::Ident) && input.peek2(syn::Token![=]) {
+            let name: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            let value: syn::Expr = input.parse()?;
+            let option = match name.to_string().as_str() {
+                "timeout" => &mut options.timeout,
+                "max_concurrency" => &mut options.max_concurrency,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        name,
+                        "unknown option, only `timeout` and `max_concurrency` are supported",
+                    ));
+                }
+            };
+            if option.replace(value).is_some() {
+                return Err(syn::Error::new_spanned(name, "option given more than once"));
+            }
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(options)
+    }
 }
 
 fn generate_execute_logic(
     session: &syn::Expr,
     callback: &syn::Expr,
+    options: &ExecuteOptions,
     functions: &syn::punctuated::Punctuated<syn::Path, syn::Token![,]>,
 ) -> TokenStream {
     let num_funcs = functions.len();
 
+    // These macros can't ask for approval or pass context, so functions needing either are
+    // rejected at compile time.
+    let approval_checks = functions.iter().map(|path| {
+        let name = quote!(#path).to_string().replace(' ', "");
+        let approval_message = format!(
+            "`{name}` requires approval, run it by a `ToolRegistry` with an approver instead"
+        );
+        let context_message = format!(
+            "`{name}` has `#[context]` parameters, run it by a `ToolRegistry` with the context set instead"
+        );
+        quote! {
+            const _: () = assert!(
+                !<#path as gemini_client_api::gemini::utils::GeminiFunction>::REQUIRES_APPROVAL,
+                #approval_message
+            );
+            const _: () = assert!(
+                !<#path as gemini_client_api::gemini::utils::GeminiFunction>::USES_CONTEXT,
+                #context_message
+            );
+        }
+    });
+
+    let max_concurrency = match &options.max_concurrency {
+        Some(max_concurrency) => quote! { #max_concurrency },
+        None => quote! { futures.len() },
+    };
+    let timeout = options.timeout.as_ref().map(|timeout| {
+        quote! { let execute_timeout: std::time::Duration = #timeout; }
+    });
+
     let match_arms = functions.iter().enumerate().map(|(i, path)| {
         let name_str = path.segments.last().unwrap().ident.to_string();
+        let execute = match &options.timeout {
+            Some(_) => quote! {
+                gemini_client_api::gemini::utils::run_with_timeout(#name_str, execute_timeout, #path::execute(&args)).await
+            },
+            None => quote! { #path::execute(&args).await },
+        };
         quote! {
             #name_str => {
                 let args = call.args().clone().unwrap_or(gemini_client_api::serde_json::json!({}));
                 let fut: gemini_client_api::futures::future::BoxFuture<'static, (usize, String, Result<gemini_client_api::serde_json::Value, String>)> = Box::pin(async move {
-                    (#i, #name_str.to_string(), #path::execute(&args).await)
+                    (#i, #name_str.to_string(), #execute)
                 });
                 futures.push(fut);
             }
@@ -452,6 +617,8 @@ fn generate_execute_logic(
 
     let expanded = quote! {
         {
+            #(#approval_checks)*
+            #timeout
             let mut results_array = vec![None; #num_funcs];
             // Define callback here to ensure it's available
             let mut result_callback = #callback;
@@ -467,7 +634,14 @@ fn generate_execute_logic(
                     }
                 }
                 if !futures.is_empty() {
-                    let results = gemini_client_api::futures::future::join_all(futures).await;
+                    let max_concurrency: usize = #max_concurrency;
+                    let results: Vec<_> = gemini_client_api::futures::StreamExt::collect(
+                        gemini_client_api::futures::StreamExt::buffered(
+                            gemini_client_api::futures::stream::iter(futures),
+                            max_concurrency.max(1),
+                        ),
+                    )
+                    .await;
                     for (idx, name, res) in results {
                         // Invoke callback regardless of success or failure
                         let val_to_add = result_callback(name.clone(), res.clone());
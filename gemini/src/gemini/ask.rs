@@ -1,13 +1,38 @@
-use super::error::GeminiResponseError;
+use super::error::{GeminiError, GeminiResponseError};
 use super::types::caching::{CachedContent, CachedContentList, CachedContentUpdate};
+use super::types::embedding::{
+    BatchEmbedContentsRequestBody, BatchEmbedContentsResponse, ContentEmbedding,
+    EmbedContentRequest, EmbedContentResponse,
+};
+use super::types::models::{ModelInfo, ModelList};
 use super::types::request::*;
 use super::types::response::*;
 use super::types::sessions::Session;
-use reqwest::Client;
+use super::utils::GeminiSchema;
+use futures::{Stream, TryStreamExt, stream};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use serde_json::{Value, json};
+use std::sync::Arc;
 use std::time::Duration;
-
-const BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+mod agent;
+mod batch;
+mod compaction;
+mod endpoint;
+mod file_promotion;
+mod files;
+mod rate_limit;
+mod retry;
+mod token_counter;
+pub use agent::{
+    AgentConfig, AgentRun, FunctionCallHook, FunctionCallRecord, FunctionExecutor, ResponseHook,
+};
+pub use compaction::Compaction;
+pub use endpoint::{ApiVersion, Endpoint};
+pub use file_promotion::FilePromotion;
+pub use rate_limit::{RateLimitPermit, RateLimiter};
+pub use retry::RetryPolicy;
+pub use token_counter::ApiTokenCounter;
 
 /// The main client for interacting with the Gemini API.
 #[derive(Clone, Default, Debug)]
@@ -21,6 +46,11 @@ pub struct Gemini {
     tools: Option<Vec<Tool>>,
     tool_config: Option<ToolConfig>,
     cached_content: Option<String>,
+    endpoint: Endpoint,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    compaction: Option<Arc<Compaction>>,
+    file_promotion: Option<FilePromotion>,
 }
 
 impl Gemini {
@@ -48,6 +78,11 @@ impl Gemini {
             tools: None,
             tool_config: None,
             cached_content: None,
+            endpoint: Endpoint::default(),
+            retry_policy: None,
+            rate_limiter: None,
+            compaction: None,
+            file_promotion: None,
         }
     }
     /// Creates a new `Gemini` client with a custom API timeout.
@@ -74,6 +109,11 @@ impl Gemini {
             tools: None,
             tool_config: None,
             cached_content: None,
+            endpoint: Endpoint::default(),
+            retry_policy: None,
+            rate_limiter: None,
+            compaction: None,
+            file_promotion: None,
         }
     }
     /// Creates a new `Gemini` client with a custom API reqwest::Client.
@@ -99,6 +139,11 @@ impl Gemini {
             tools: None,
             tool_config: None,
             cached_content: None,
+            endpoint: Endpoint::default(),
+            retry_policy: None,
+            rate_limiter: None,
+            compaction: None,
+            file_promotion: None,
         }
     }
     /// Returns a mutable reference to the generation configuration.
@@ -115,14 +160,62 @@ impl Gemini {
         self.tool_config = Some(config);
         self
     }
-    pub fn set_thinking_config(mut self, config: ThinkingConfig) -> Self {
+    pub fn set_thinking_config(self, config: ThinkingConfig) -> Self {
+        self.merge_generation_config(GenerationConfig::default().set_thinking_config(config))
+    }
+    /// Makes a TTS model, like "gemini-2.5-flash-preview-tts", reply with speech of `config`.
+    /// Sets `responseModalities` to `[Modality::Audio]`. Audio is in `Chat::get_audios` of
+    /// replies, see `InlineData::save_wav`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run() {
+    /// use gemini_client_api::gemini::ask::Gemini;
+    /// use gemini_client_api::gemini::types::request::SpeechConfig;
+    /// use gemini_client_api::gemini::types::sessions::Session;
+    /// let ai = Gemini::new("API_KEY", "gemini-2.5-flash-preview-tts", None)
+    ///     .set_speech_config(SpeechConfig::new_multi_speaker([("Joe", "Kore"), ("Jane", "Puck")]));
+    /// let mut session = Session::new(2);
+    /// session.ask("TTS the following conversation:\nJoe: How's it going?\nJane: Not too bad!");
+    /// let response = ai.ask(&mut session).await.unwrap();
+    /// let audio = response.get_chat().get_audios().next().unwrap();
+    /// audio.save_wav("conversation").await.unwrap();
+    /// # }
+    /// ```
+    pub fn set_speech_config(self, config: SpeechConfig) -> Self {
+        self.merge_generation_config(
+            GenerationConfig::default()
+                .set_response_modalities(vec![Modality::Audio])
+                .set_speech_config(config),
+        )
+    }
+    /// Kinds of output the model should generate, like `[Modality::Text, Modality::Image]` for
+    /// image generation. Images are in `Chat::get_images` of replies.
+    pub fn set_response_modalities(self, modalities: Vec<Modality>) -> Self {
+        self.merge_generation_config(
+            GenerationConfig::default().set_response_modalities(modalities),
+        )
+    }
+    /// Sets the fields set in `config`, keeping other fields of the generation configuration
+    /// as they are.
+    ///
+    /// # Example
+    /// ```
+    /// use gemini_client_api::gemini::ask::Gemini;
+    /// use gemini_client_api::gemini::types::request::{GenerationConfig, ThinkingConfig};
+    /// let ai = Gemini::new("API_KEY", "gemini-2.5-flash", None)
+    ///     .set_thinking_config(ThinkingConfig::new_disable_thinking())
+    ///     .merge_generation_config(GenerationConfig::default().set_temperature(0.2).set_seed(7));
+    /// ```
+    pub fn merge_generation_config(mut self, config: GenerationConfig) -> Self {
         if let Value::Object(map) = self.set_generation_config() {
-            if let Ok(thinking_value) = serde_json::to_value(config) {
-                map.insert("thinking_config".to_string(), thinking_value);
-            }
+            config.merge_into(map);
         }
         self
     }
+    pub fn get_generation_config(&self) -> Option<&Value> {
+        self.generation_config.as_ref()
+    }
     pub fn set_model(mut self, model: impl Into<String>) -> Self {
         self.model = model.into();
         self
@@ -141,6 +234,45 @@ impl Gemini {
         self.api_key = api_key.into();
         self
     }
+    /// Sets where requests are sent, like a proxy, regional gateway or mock server.
+    /// Defaults to `Endpoint::default()` which is Google's `v1beta` API.
+    pub fn set_endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+    pub fn get_endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+    fn url(&self, path: &str) -> String {
+        format!("{}?key={}", self.endpoint.url(path), self.api_key)
+    }
+    /// `url` of `path` with `query` URL-encoded and appended, skipping `None` values.
+    pub(crate) fn url_with_query<'a>(
+        &self,
+        path: &str,
+        query: impl IntoIterator<Item = (&'a str, Option<String>)>,
+    ) -> String {
+        let req_url = self.url(path);
+        let query = query
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)));
+        match reqwest::Url::parse_with_params(&req_url, query) {
+            Ok(url) => url.into(),
+            // Sending to an invalid URL fails with the error.
+            Err(_) => req_url,
+        }
+    }
+    fn request_body<'a>(&'a self, history: &'a [&'a Chat]) -> GeminiRequestBody<'a> {
+        GeminiRequestBody::new(
+            self.sys_prompt.as_ref(),
+            self.tools.as_deref(),
+            history,
+            self.generation_config.as_ref(),
+            self.safety_settings.as_deref(),
+            self.tool_config.as_ref(),
+            self.cached_content.clone(),
+        )
+    }
     /// Sets the response format to JSON mode with a specific schema.
     ///
     /// To use a Rust struct as a schema, decorate it with `#[gemini_schema]` and pass
@@ -148,16 +280,23 @@ impl Gemini {
     ///
     /// # Arguments
     /// * `schema` - The JSON schema for the response. See [Gemini Schema docs](https://ai.google.dev/api/caching#Schema).
-    pub fn set_json_mode(mut self, schema: Value) -> Self {
-        let config = self.set_generation_config();
-        config["response_mime_type"] = "application/json".into();
-        config["response_schema"] = schema.into();
-        self
+    pub fn set_json_mode(self, schema: Value) -> Self {
+        self.merge_generation_config(
+            GenerationConfig::default()
+                .set_response_mime_type("application/json")
+                .set_response_schema(schema),
+        )
     }
     pub fn remove_json_mode(mut self) -> Self {
-        if let Some(ref mut generation_config) = self.generation_config {
-            generation_config["response_schema"] = None::<Value>.into();
-            generation_config["response_mime_type"] = None::<Value>.into();
+        if let Some(Value::Object(generation_config)) = &mut self.generation_config {
+            for key in [
+                "responseSchema",
+                "response_schema",
+                "responseMimeType",
+                "response_mime_type",
+            ] {
+                generation_config.remove(key);
+            }
         }
         self
     }
@@ -180,93 +319,148 @@ impl Gemini {
         self
     }
 
+    /// Sets the policy used to retry requests failed with a retryable `Status`.
+    /// `None` (default) returns the first error as it is.
+    pub fn set_retry_policy(mut self, retry_policy: Option<RetryPolicy>) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+    pub fn get_retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+    /// Sets the limiter that requests to generate content wait for. Share one limiter among
+    /// all clients using the same API key.
+    pub fn set_rate_limiter(mut self, rate_limiter: Option<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+    pub fn get_rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+    /// Sets compaction of long sessions by summarizing their oldest chats before each `ask`.
+    pub fn set_compaction(mut self, compaction: Option<Compaction>) -> Self {
+        self.compaction = compaction.map(Arc::new);
+        self
+    }
+    pub fn get_compaction(&self) -> Option<&Compaction> {
+        self.compaction.as_deref()
+    }
+    /// Sets uploading of large `InlineData` of sessions to the Files API before each `ask`.
+    pub fn set_file_promotion(mut self, file_promotion: Option<FilePromotion>) -> Self {
+        self.file_promotion = file_promotion;
+        self
+    }
+    pub fn get_file_promotion(&self) -> Option<&FilePromotion> {
+        self.file_promotion.as_ref()
+    }
+    /// Sends the request built by `request`, rebuilding and resending it as long as
+    /// `retry_policy` allows.
+    async fn send(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<Response, GeminiResponseError> {
+        self.send_inner(request, false, true)
+            .await
+            .map(|(response, _)| response)
+    }
+    /// Same as `send` for requests creating something, which are retried only as
+    /// `RetryPolicy` allows for non-idempotent requests.
+    async fn send_non_idempotent(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<Response, GeminiResponseError> {
+        self.send_inner(request, false, false)
+            .await
+            .map(|(response, _)| response)
+    }
+    /// Same as `send` but every attempt waits for `rate_limiter`. Returned permit keeps the
+    /// request in flight.
+    async fn send_rate_limited(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<(Response, Option<RateLimitPermit>), GeminiResponseError> {
+        self.send_inner(request, true, true).await
+    }
+    async fn send_inner(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+        rate_limited: bool,
+        idempotent: bool,
+    ) -> Result<(Response, Option<RateLimitPermit>), GeminiResponseError> {
+        let mut attempt = 1;
+        loop {
+            let permit = match &self.rate_limiter {
+                Some(rate_limiter) if rate_limited => Some(rate_limiter.acquire().await),
+                _ => None,
+            };
+            let response = request()
+                .send()
+                .await
+                .map_err(GeminiResponseError::ReqwestError)?;
+            if response.status().is_success() {
+                return Ok((response, permit));
+            }
+            let code = response.status();
+            let body = response
+                .text()
+                .await
+                .map_err(GeminiResponseError::ReqwestError)?;
+            let error = GeminiError::from_body(code, &body);
+            drop(permit);
+            match self
+                .retry_policy
+                .as_ref()
+                .and_then(|policy| match idempotent {
+                    true => policy.delay(attempt, &error.error),
+                    false => policy.delay_non_idempotent(attempt, &error.error),
+                }) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(GeminiResponseError::StatusNotOk(error)),
+            }
+            attempt += 1;
+        }
+    }
+
     // Cache management methods
 
     pub async fn create_cache(
         &self,
         cached_content: &CachedContent,
     ) -> Result<CachedContent, GeminiResponseError> {
-        let req_url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/cachedContents?key={}",
-            self.api_key
-        );
+        let req_url = self.url("cachedContents");
 
         let response = self
-            .client
-            .post(req_url)
-            .json(cached_content)
-            .send()
-            .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-
-        if !response.status().is_success() {
-            let error = response
-                .json()
-                .await
-                .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-            return Err(GeminiResponseError::StatusNotOk(error));
-        }
+            .send_non_idempotent(|| self.client.post(&req_url).json(cached_content))
+            .await?;
 
         let cached_content: CachedContent = response
             .json()
             .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
+            .map_err(GeminiResponseError::ReqwestError)?;
         Ok(cached_content)
     }
 
     pub async fn list_caches(&self) -> Result<CachedContentList, GeminiResponseError> {
-        let req_url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/cachedContents?key={}",
-            self.api_key
-        );
+        let req_url = self.url("cachedContents");
 
-        let response = self
-            .client
-            .get(req_url)
-            .send()
-            .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-
-        if !response.status().is_success() {
-            let error = response
-                .json()
-                .await
-                .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-            return Err(GeminiResponseError::StatusNotOk(error));
-        }
+        let response = self.send(|| self.client.get(&req_url)).await?;
 
         let list: CachedContentList = response
             .json()
             .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
+            .map_err(GeminiResponseError::ReqwestError)?;
         Ok(list)
     }
 
     pub async fn get_cache(&self, name: &str) -> Result<CachedContent, GeminiResponseError> {
-        let req_url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/{}?key={}",
-            name, self.api_key
-        );
-
-        let response = self
-            .client
-            .get(req_url)
-            .send()
-            .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
+        let req_url = self.url(name);
 
-        if !response.status().is_success() {
-            let error = response
-                .json()
-                .await
-                .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-            return Err(GeminiResponseError::StatusNotOk(error));
-        }
+        let response = self.send(|| self.client.get(&req_url)).await?;
 
         let cached_content: CachedContent = response
             .json()
             .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
+            .map_err(GeminiResponseError::ReqwestError)?;
         Ok(cached_content)
     }
 
@@ -275,56 +469,212 @@ impl Gemini {
         name: &str,
         update: &CachedContentUpdate,
     ) -> Result<CachedContent, GeminiResponseError> {
-        let req_url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/{}?key={}",
-            name, self.api_key
-        );
+        let req_url = self.url(name);
 
         let response = self
-            .client
-            .patch(req_url)
-            .json(update)
-            .send()
-            .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-
-        if !response.status().is_success() {
-            let error = response
-                .json()
-                .await
-                .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-            return Err(GeminiResponseError::StatusNotOk(error));
-        }
+            .send(|| self.client.patch(&req_url).json(update))
+            .await?;
 
         let cached_content: CachedContent = response
             .json()
             .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
+            .map_err(GeminiResponseError::ReqwestError)?;
         Ok(cached_content)
     }
 
     pub async fn delete_cache(&self, name: &str) -> Result<(), GeminiResponseError> {
-        let req_url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/{}?key={}",
-            name, self.api_key
-        );
+        let req_url = self.url(name);
 
-        let response = self
-            .client
-            .delete(req_url)
-            .send()
+        self.send(|| self.client.delete(&req_url)).await?;
+
+        Ok(())
+    }
+
+    // Model methods
+
+    /// Lists models available to the API key, fetching pages of `page_size` (default 50,
+    /// upto 1000) as the stream is read.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini) {
+    /// use futures::TryStreamExt;
+    /// let models: Vec<_> = gemini.list_models(None).try_collect().await.unwrap();
+    /// for model in models.iter().filter(|model| model.supports("generateContent")) {
+    ///     println!("{} accepts {:?} tokens", model.get_id(), model.input_token_limit());
+    /// }
+    /// # }
+    /// ```
+    pub fn list_models(
+        &self,
+        page_size: Option<u32>,
+    ) -> impl Stream<Item = Result<ModelInfo, GeminiResponseError>> + '_ {
+        stream::try_unfold(
+            Some(None),
+            move |page_token: Option<Option<String>>| async move {
+                let Some(page_token) = page_token else {
+                    return Ok(None);
+                };
+                let req_url = self.url_with_query(
+                    "models",
+                    [
+                        ("pageSize", page_size.map(|size| size.to_string())),
+                        ("pageToken", page_token),
+                    ],
+                );
+
+                let response = self.send(|| self.client.get(&req_url)).await?;
+
+                let list: ModelList = response
+                    .json()
+                    .await
+                    .map_err(GeminiResponseError::ReqwestError)?;
+                let (models, next_page_token) = list.into_parts();
+                Ok(Some((
+                    stream::iter(models.into_iter().map(Ok)),
+                    next_page_token.map(Some),
+                )))
+            },
+        )
+        .try_flatten()
+    }
+
+    /// Gets information of model `name` like "gemini-2.5-flash" or "models/gemini-2.5-flash".
+    pub async fn get_model(&self, name: &str) -> Result<ModelInfo, GeminiResponseError> {
+        let req_url = match name.starts_with("models/") {
+            true => self.url(name),
+            false => self.url(&format!("models/{name}")),
+        };
+
+        let response = self.send(|| self.client.get(&req_url)).await?;
+
+        let model: ModelInfo = response
+            .json()
             .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
+            .map_err(GeminiResponseError::ReqwestError)?;
+        Ok(model)
+    }
+    /// Gets information of the model of this client, e.g. to check it exists or to size token
+    /// budgets by `ModelInfo::input_token_limit`.
+    pub async fn get_model_info(&self) -> Result<ModelInfo, GeminiResponseError> {
+        self.get_model(&self.model).await
+    }
 
-        if !response.status().is_success() {
-            let error = response
-                .json()
-                .await
-                .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-            return Err(GeminiResponseError::StatusNotOk(error));
+    // Embedding methods
+
+    /// Embeds `request` using the model of this client, like "gemini-embedding-001".
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini) {
+    /// let embedding = gemini.embed("What is the meaning of life?").await.unwrap();
+    /// println!("{:?}", embedding.values);
+    /// # }
+    /// ```
+    pub async fn embed(
+        &self,
+        request: impl Into<EmbedContentRequest>,
+    ) -> Result<ContentEmbedding, GeminiResponseError> {
+        let req_url = self.url(&format!("models/{}:embedContent", self.model));
+        let request = request.into();
+
+        let (response, _permit) = self
+            .send_rate_limited(|| self.client.post(&req_url).json(&request))
+            .await?;
+
+        let response: EmbedContentResponse = response
+            .json()
+            .await
+            .map_err(GeminiResponseError::ReqwestError)?;
+        Ok(response.embedding)
+    }
+
+    /// Embeds all `requests` in a single request.
+    /// # Returns
+    /// Embeddings in the same order as `requests`.
+    pub async fn embed_batch(
+        &self,
+        mut requests: Vec<EmbedContentRequest>,
+    ) -> Result<Vec<ContentEmbedding>, GeminiResponseError> {
+        let req_url = self.url(&format!("models/{}:batchEmbedContents", self.model));
+        for request in requests.iter_mut() {
+            request.set_model(format!("models/{}", self.model));
         }
+        let body = BatchEmbedContentsRequestBody::new(&requests);
 
-        Ok(())
+        let (response, _permit) = self
+            .send_rate_limited(|| self.client.post(&req_url).json(&body))
+            .await?;
+
+        let response: BatchEmbedContentsResponse = response
+            .json()
+            .await
+            .map_err(GeminiResponseError::ReqwestError)?;
+        Ok(response.embeddings)
+    }
+
+    /// Counts the tokens of exactly what `ask` would send for `session`, including system
+    /// instruction, tools and cached content, without generating anything.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini, mut session: gemini_client_api::gemini::types::sessions::Session) {
+    /// let tokens = gemini.count_tokens(session.ask("Hi")).await.unwrap();
+    /// if tokens.total_tokens < 100_000 {
+    ///     gemini.ask(&mut session).await.unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub async fn count_tokens(
+        &self,
+        session: &Session,
+    ) -> Result<CountTokensResponse, GeminiResponseError> {
+        let req_url = self.url(&format!("models/{}:countTokens", self.model));
+        let history = session.get_history();
+        let body = CountTokensRequestBody::new(
+            None,
+            Some(GenerateContentRequest::new(
+                format!("models/{}", self.model),
+                self.request_body(history.as_slice()),
+            )),
+        );
+
+        let response = self.send(|| self.client.post(&req_url).json(&body)).await?;
+
+        response
+            .json()
+            .await
+            .map_err(GeminiResponseError::ReqwestError)
+    }
+
+    /// Counts the tokens of `contents` alone, without system instruction, tools or cached
+    /// content.
+    pub async fn count_contents_tokens(
+        &self,
+        contents: &[&Chat],
+    ) -> Result<CountTokensResponse, GeminiResponseError> {
+        self.count_contents_tokens_inner(contents, false).await
+    }
+    async fn count_contents_tokens_inner(
+        &self,
+        contents: &[&Chat],
+        rate_limited: bool,
+    ) -> Result<CountTokensResponse, GeminiResponseError> {
+        let req_url = self.url(&format!("models/{}:countTokens", self.model));
+        let body = CountTokensRequestBody::new(Some(contents), None);
+
+        let (response, _permit) = self
+            .send_inner(
+                || self.client.post(&req_url).json(&body),
+                rate_limited,
+                true,
+            )
+            .await?;
+
+        response
+            .json()
+            .await
+            .map_err(GeminiResponseError::ReqwestError)
     }
 
     /// Sends a prompt to the model and waits for the full response.
@@ -333,6 +683,8 @@ impl Gemini {
     ///
     /// # Errors
     /// Returns `GeminiResponseError::NothingToRespond` if the last message in history is from the model.
+    /// Returns `GeminiResponseError::Blocked` if the prompt or every candidate was blocked. The
+    /// `session` is then not updated.
     pub async fn ask(&self, session: &mut Session) -> Result<GeminiResponse, GeminiResponseError> {
         if session
             .get_last_chat()
@@ -340,41 +692,114 @@ impl Gemini {
         {
             return Err(GeminiResponseError::NothingToRespond);
         }
-        let req_url = format!(
-            "{BASE_URL}/{}:generateContent?key={}",
-            self.model, self.api_key
-        );
-
-        let response = self
-            .client
-            .post(req_url)
-            .json(&GeminiRequestBody::new(
-                self.sys_prompt.as_ref(),
-                self.tools.as_deref(),
-                &session.get_history().as_slice(),
-                self.generation_config.as_ref(),
-                self.safety_settings.as_deref(),
-                self.tool_config.as_ref(),
-                self.cached_content.clone(),
-            ))
-            .send()
-            .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-
-        if !response.status().is_success() {
-            let error = response
-                .json()
-                .await
-                .map_err(|e| GeminiResponseError::ReqwestError(e))?;
-            return Err(GeminiResponseError::StatusNotOk(error));
+        if let Some(compaction) = &self.compaction {
+            compaction.compact(session).await?;
+        }
+        if let Some(file_promotion) = &self.file_promotion {
+            file_promotion.promote(self, session).await?;
         }
+        let req_url = self.url(&format!("models/{}:generateContent", self.model));
+        let history = session.get_history();
+        let body = self.request_body(history.as_slice());
+
+        let (response, permit) = self
+            .send_rate_limited(|| self.client.post(&req_url).json(&body))
+            .await?;
 
         let reply = GeminiResponse::new(response)
             .await
-            .map_err(|e| GeminiResponseError::ReqwestError(e))?;
+            .map_err(GeminiResponseError::ReqwestError)?;
+        if let Some(permit) = permit {
+            permit.record_tokens(reply.usage_metadata.total_token_count.unwrap_or(0).into());
+        }
+        if reply.is_blocked() {
+            return Err(GeminiResponseError::Blocked(Box::new(reply)));
+        }
         session.update(&reply);
         Ok(reply)
     }
+    /// Asks for a reply in JSON of schema `T::gemini_schema()` and deserializes it. The schema
+    /// is applied to this call only.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini) {
+    /// use gemini_client_api::gemini::types::sessions::Session;
+    /// use gemini_client_api::gemini::utils::{GeminiSchema, gemini_schema};
+    /// use serde::Deserialize;
+    /// #[gemini_schema]
+    /// #[derive(Deserialize)]
+    /// struct Capital {
+    ///     country: String,
+    ///     city: String,
+    /// }
+    /// let mut session = Session::new(6);
+    /// session.ask("What is the capital of France?");
+    /// let (capital, _response) = gemini.ask_structured::<Capital>(&mut session).await.unwrap();
+    /// println!("{}", capital.city);
+    /// # }
+    /// ```
+    pub async fn ask_structured<T: GeminiSchema + DeserializeOwned>(
+        &self,
+        session: &mut Session,
+    ) -> Result<(T, GeminiResponse), GeminiResponseError> {
+        self.ask_structured_with_reprompt(session, 1).await
+    }
+    /// Same as `ask_structured` but if the reply can't be deserialized to `T`, the model is told
+    /// the error and asked again, upto `max_attempts` asks in total. The re-prompt turns are
+    /// removed from `session` afterwards, leaving the prompt and the final reply.
+    ///
+    /// # Errors
+    /// `GeminiResponseError::InvalidStructuredOutput` with the last reply if every attempt
+    /// failed. On any error, `session` is left with the prompt and without replies.
+    pub async fn ask_structured_with_reprompt<T: GeminiSchema + DeserializeOwned>(
+        &self,
+        session: &mut Session,
+        max_attempts: u32,
+    ) -> Result<(T, GeminiResponse), GeminiResponseError> {
+        let gemini = self.clone().set_json_mode(T::gemini_schema());
+        // Re-prompts may pop the prompt itself off the front by the history limits.
+        let snapshot = session.clone();
+        // The model must see its invalid reply when re-prompted.
+        *session = std::mem::take(session).set_remember_reply(true);
+        let mut attempt = 1;
+        let result = loop {
+            let response = match gemini.ask(session).await {
+                Ok(response) => response,
+                Err(error) => break Err(error),
+            };
+            let position = session
+                .get_candidate_selector()
+                .select(response.get_candidates())
+                .unwrap_or(0);
+            let parts = response
+                .get_candidates()
+                .get(position)
+                .map_or(&[][..], |candidate| candidate.content.parts());
+            match GeminiResponse::parse_json::<T>(parts) {
+                Ok(structured) => break Ok((structured, response)),
+                Err(error) if attempt >= max_attempts => {
+                    break Err(GeminiResponseError::InvalidStructuredOutput(
+                        error,
+                        Box::new(response),
+                    ));
+                }
+                Err(error) => {
+                    session.ask(format!(
+                        "Your reply is not valid JSON of the required schema: {error}. \
+Reply again with only the corrected JSON."
+                    ));
+                }
+            }
+            attempt += 1;
+        };
+
+        *session = snapshot;
+        if let Ok((_, response)) = &result {
+            session.update(response);
+        }
+        result
+    }
     /// # Warning
     /// You must read the response stream to get reply stored context in `session`.
     /// `data_extractor` is used to extract data that you get as a stream of futures.
@@ -390,7 +815,7 @@ impl Gemini {
     ///```
     pub async fn ask_as_stream_with_extractor<F, StreamType>(
         &self,
-        session: Session,
+        mut session: Session,
         data_extractor: F,
     ) -> Result<ResponseStream<F, StreamType>, (Session, GeminiResponseError)>
     where
@@ -402,42 +827,38 @@ impl Gemini {
         {
             return Err((session, GeminiResponseError::NothingToRespond));
         }
+        if let Some(compaction) = &self.compaction
+            && let Err(e) = compaction.compact(&mut session).await
+        {
+            return Err((session, e));
+        }
+        if let Some(file_promotion) = &self.file_promotion
+            && let Err(e) = file_promotion.promote(self, &mut session).await
+        {
+            return Err((session, e));
+        }
         let req_url = format!(
-            "{BASE_URL}/{}:streamGenerateContent?alt=sse&key={}",
-            self.model, self.api_key
+            "{}?alt=sse&key={}",
+            self.endpoint
+                .url(&format!("models/{}:streamGenerateContent", self.model)),
+            self.api_key
         );
+        let history = session.get_history();
+        let body = self.request_body(history.as_slice());
 
-        let request = self
-            .client
-            .post(req_url)
-            .json(&GeminiRequestBody::new(
-                self.sys_prompt.as_ref(),
-                self.tools.as_deref(),
-                session.get_history().as_slice(),
-                self.generation_config.as_ref(),
-                self.safety_settings.as_deref(),
-                self.tool_config.as_ref(),
-                self.cached_content.clone(),
-            ))
-            .send()
-            .await;
-        let response = match request {
+        let (response, permit) = match self
+            .send_rate_limited(|| self.client.post(&req_url).json(&body))
+            .await
+        {
             Ok(response) => response,
-            Err(e) => return Err((session, GeminiResponseError::ReqwestError(e))),
+            Err(e) => return Err((session, e)),
         };
 
-        if !response.status().is_success() {
-            let error = match response.json().await {
-                Ok(response) => response,
-                Err(e) => return Err((session, GeminiResponseError::ReqwestError(e))),
-            };
-            return Err((session, GeminiResponseError::StatusNotOk(error)));
-        }
-
         Ok(ResponseStream::new(
             Box::new(response.bytes_stream()),
             session,
             data_extractor,
+            permit,
         ))
     }
     /// Sends a prompt to the model and returns a stream of responses.
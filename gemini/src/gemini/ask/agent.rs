@@ -0,0 +1,356 @@
+use super::Gemini;
+use crate::gemini::error::GeminiResponseError;
+use crate::gemini::types::request::{
+    FunctionCall, FunctionCallingConfig, FunctionCallingMode, Role, ToolConfig,
+};
+use crate::gemini::types::response::GeminiResponse;
+use crate::gemini::types::sessions::Session;
+use crate::gemini::utils::run_with_timeout;
+use futures::{StreamExt, stream};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runs function calls requested by the model in `Gemini::run_agent`.
+///
+/// Implemented by `ToolRegistry` and for closures taking the `FunctionCall` and returning a
+/// future of the result.
+/// `Err` is sent to the model as `{"Error": error}`.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::types::request::FunctionCall;
+/// use serde_json::{Value, json};
+/// let executor = |call: FunctionCall| async move {
+///     match call.name().as_str() {
+///         "get_time" => Ok::<Value, String>(json!("12:00")),
+///         name => Err(format!("unknown function {name}")),
+///     }
+/// };
+/// ```
+pub trait FunctionExecutor {
+    fn execute(&self, call: &FunctionCall) -> impl Future<Output = Result<Value, String>> + Send;
+    /// Runs `call` like `execute`, cancelling it after `timeout` with an error. Executors waiting
+    /// for approval of calls, like `ToolRegistry`, exclude the wait from `timeout`.
+    fn execute_with_timeout(
+        &self,
+        call: &FunctionCall,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Value, String>> + Send {
+        run_with_timeout(call.name(), timeout, self.execute(call))
+    }
+    /// Maximum calls of a reply run at once. Unlimited by default.
+    fn get_max_concurrency(&self) -> Option<usize> {
+        None
+    }
+}
+impl<F, Fut> FunctionExecutor for F
+where
+    F: Fn(FunctionCall) -> Fut,
+    Fut: Future<Output = Result<Value, String>> + Send,
+{
+    fn execute(&self, call: &FunctionCall) -> impl Future<Output = Result<Value, String>> + Send {
+        self(call.clone())
+    }
+}
+
+/// A function call made by `Gemini::run_agent`.
+#[derive(Debug, Clone)]
+pub struct FunctionCallRecord {
+    /// Index of the model reply that requested the call, from 0.
+    pub iteration: usize,
+    pub call: FunctionCall,
+    pub result: Result<Value, String>,
+}
+
+/// Outcome of `Gemini::run_agent`.
+#[derive(Debug, Clone)]
+pub struct AgentRun {
+    /// Last reply of the model.
+    pub response: GeminiResponse,
+    /// All function calls made, in order.
+    pub function_calls: Vec<FunctionCallRecord>,
+    /// Count of replies asked for.
+    pub iterations: usize,
+}
+
+pub type ResponseHook = dyn Fn(usize, &GeminiResponse) + Send + Sync;
+pub type FunctionCallHook = dyn Fn(&FunctionCallRecord) + Send + Sync;
+
+/// Settings of `Gemini::run_agent`.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::ask::AgentConfig;
+/// let config = AgentConfig::default()
+///     .set_max_iterations(5)
+///     .set_timeout(std::time::Duration::from_secs(30))
+///     .set_on_function_call(|record| println!("{}: {:?}", record.call.name(), record.result));
+/// ```
+#[derive(Clone)]
+pub struct AgentConfig {
+    max_iterations: usize,
+    timeout: Option<Duration>,
+    max_concurrency: Option<usize>,
+    on_response: Option<Arc<ResponseHook>>,
+    on_function_call: Option<Arc<FunctionCallHook>>,
+}
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 10,
+            timeout: None,
+            max_concurrency: None,
+            on_response: None,
+            on_function_call: None,
+        }
+    }
+}
+impl std::fmt::Debug for AgentConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentConfig")
+            .field("max_iterations", &self.max_iterations)
+            .field("timeout", &self.timeout)
+            .field("max_concurrency", &self.max_concurrency)
+            .field("on_response", &self.on_response.is_some())
+            .field("on_function_call", &self.on_function_call.is_some())
+            .finish()
+    }
+}
+impl AgentConfig {
+    /// Maximum replies to ask for, including the final one. 10 by default.
+    pub fn set_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+    /// Time after which a function call is cancelled and gets an error as response. No timeout
+    /// by default. Time spent for approval by a `ToolRegistry` is excluded, and the lower of it
+    /// and the registry's timeout of the function applies.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Maximum calls of a reply run at once, `1` to run them one by one in order. Unlimited by
+    /// default. The lower of it and `FunctionExecutor::get_max_concurrency` applies.
+    pub fn set_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency.max(1));
+        self
+    }
+    /// Called with the iteration and the reply each time the model replies.
+    pub fn set_on_response(
+        mut self,
+        hook: impl Fn(usize, &GeminiResponse) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_response = Some(Arc::new(hook));
+        self
+    }
+    /// Called after each function call with its result.
+    pub fn set_on_function_call(
+        mut self,
+        hook: impl Fn(&FunctionCallRecord) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_function_call = Some(Arc::new(hook));
+        self
+    }
+    pub fn get_max_iterations(&self) -> usize {
+        self.max_iterations
+    }
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+    pub fn get_max_concurrency(&self) -> Option<usize> {
+        self.max_concurrency
+    }
+}
+
+impl Gemini {
+    /// Asks, runs the function calls of the reply by `executor` and asks again with their
+    /// results, until the model replies without function calls. Calls of a reply run
+    /// concurrently, bounded by `config` and `executor.get_max_concurrency()`, and time out
+    /// after `config.get_timeout()`.
+    ///
+    /// `ToolConfig` is respected:
+    /// * `FunctionCallingMode::None` - Function calls are never run, the first reply is final.
+    ///   Its calls get an error as response.
+    /// * `FunctionCallingMode::Any` - Applies to the first reply only, as every reply would be
+    ///   a function call otherwise. Later replies use `FunctionCallingMode::Auto`.
+    /// * `allowed_function_names` - Calls to other functions are not run and get an error as
+    ///   response.
+    ///
+    /// # Errors
+    /// Errors of `ask`, and `GeminiResponseError::MaxIterationsReached` with the run so far if
+    /// the model still calls functions after `config.get_max_iterations()` replies. The calls
+    /// of the last reply get an error as response, so `session` can be asked again.
+    /// `AddFunctionResponseError` if `session` doesn't remember replies.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini) {
+    /// use gemini_client_api::gemini::ask::AgentConfig;
+    /// use gemini_client_api::gemini::types::request::FunctionCall;
+    /// use gemini_client_api::gemini::types::sessions::Session;
+    /// use serde_json::{Value, json};
+    /// let mut session = Session::new(20);
+    /// session.ask("What time is it?");
+    /// let run = gemini
+    ///     .run_agent(
+    ///         &mut session,
+    ///         &|call: FunctionCall| async move {
+    ///             match call.name().as_str() {
+    ///                 "get_time" => Ok::<Value, String>(json!("12:00")),
+    ///                 name => Err(format!("unknown function {name}")),
+    ///             }
+    ///         },
+    ///         AgentConfig::default(),
+    ///     )
+    ///     .await
+    ///     .unwrap();
+    /// println!("{}", run.response.get_chat().get_text_no_think(""));
+    /// # }
+    /// ```
+    pub async fn run_agent(
+        &self,
+        session: &mut Session,
+        executor: &impl FunctionExecutor,
+        config: AgentConfig,
+    ) -> Result<AgentRun, GeminiResponseError> {
+        let calling_config = self
+            .tool_config
+            .as_ref()
+            .and_then(|tool_config| tool_config.function_calling_config.as_ref());
+        let mode = calling_config.and_then(|calling_config| calling_config.mode.as_ref());
+        let allowed = calling_config
+            .and_then(|calling_config| calling_config.allowed_function_names.as_ref());
+        let follow_up = match mode {
+            Some(FunctionCallingMode::Any) => {
+                let mut follow_up = self.clone();
+                follow_up.tool_config = Some(ToolConfig {
+                    function_calling_config: Some(FunctionCallingConfig {
+                        mode: Some(FunctionCallingMode::Auto),
+                        allowed_function_names: None,
+                    }),
+                });
+                Some(follow_up)
+            }
+            _ => None,
+        };
+
+        let mut function_calls = Vec::new();
+        let mut iteration = 0;
+        loop {
+            let gemini = match &follow_up {
+                Some(follow_up) if iteration > 0 => follow_up,
+                _ => self,
+            };
+            let response = gemini.ask(session).await?;
+            if let Some(hook) = &config.on_response {
+                hook(iteration, &response);
+            }
+            // The session holds the selected candidate, which may not be the first one.
+            let calls: Vec<FunctionCall> = match session.get_last_chat() {
+                Some(chat) if *chat.role() == Role::Model => {
+                    chat.get_function_calls().cloned().collect()
+                }
+                _ => response.get_chat().get_function_calls().cloned().collect(),
+            };
+            iteration += 1;
+            if calls.is_empty() {
+                return Ok(AgentRun {
+                    response,
+                    function_calls,
+                    iterations: iteration,
+                });
+            }
+            let disabled = mode == Some(&FunctionCallingMode::None);
+            if disabled || iteration >= config.max_iterations {
+                // Calls left unanswered would make the next ask of the session fail.
+                if session
+                    .get_last_chat()
+                    .is_some_and(|chat| *chat.role() == Role::Model)
+                {
+                    let reason = match disabled {
+                        true => "function calling is disabled",
+                        false => "maximum iterations reached",
+                    };
+                    let results = calls
+                        .iter()
+                        .map(|call| Err(format!("call of `{}` was not run: {reason}", call.name())))
+                        .collect();
+                    Self::add_function_results(
+                        session,
+                        &config,
+                        &mut function_calls,
+                        iteration - 1,
+                        calls,
+                        results,
+                    )?;
+                }
+                let run = AgentRun {
+                    response,
+                    function_calls,
+                    iterations: iteration,
+                };
+                return match disabled {
+                    true => Ok(run),
+                    false => Err(GeminiResponseError::MaxIterationsReached(Box::new(run))),
+                };
+            }
+
+            let max_concurrency = [config.max_concurrency, executor.get_max_concurrency()]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap_or(calls.len())
+                .max(1);
+            let results: Vec<_> = stream::iter(calls.iter().map(async |call| match allowed {
+                Some(allowed) if !allowed.contains(call.name()) => {
+                    Err(format!("function `{}` is not allowed", call.name()))
+                }
+                _ => match config.timeout {
+                    Some(timeout) => executor.execute_with_timeout(call, timeout).await,
+                    None => executor.execute(call).await,
+                },
+            }))
+            .buffered(max_concurrency)
+            .collect()
+            .await;
+            Self::add_function_results(
+                session,
+                &config,
+                &mut function_calls,
+                iteration - 1,
+                calls,
+                results,
+            )?;
+        }
+    }
+    /// Adds `results` of `calls` to `session` as function responses and records them.
+    fn add_function_results(
+        session: &mut Session,
+        config: &AgentConfig,
+        function_calls: &mut Vec<FunctionCallRecord>,
+        iteration: usize,
+        calls: Vec<FunctionCall>,
+        results: Vec<Result<Value, String>>,
+    ) -> Result<(), GeminiResponseError> {
+        for (call, result) in calls.into_iter().zip(results) {
+            let value = match &result {
+                Ok(value) => value.clone(),
+                Err(error) => json!({"Error": error}),
+            };
+            session
+                .add_function_response(call.name(), value)
+                .map_err(GeminiResponseError::AddFunctionResponseError)?;
+            let record = FunctionCallRecord {
+                iteration,
+                call,
+                result,
+            };
+            if let Some(hook) = &config.on_function_call {
+                hook(&record);
+            }
+            function_calls.push(record);
+        }
+        Ok(())
+    }
+}
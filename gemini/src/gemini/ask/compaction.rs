@@ -0,0 +1,106 @@
+use super::Gemini;
+use crate::gemini::error::GeminiResponseError;
+use crate::gemini::types::request::{Chat, PartType};
+use crate::gemini::types::sessions::Session;
+
+const DEFAULT_INSTRUCTION: &str = "Summarize the following conversation between a user and an AI assistant. \
+Keep every fact, preference, decision, name and number needed to continue the conversation. \
+Reply with the summary only.";
+
+/// Compacts long sessions by summarizing their oldest chats instead of forgetting them.
+///
+/// Before each `ask`, if the history has `threshold` or more chats, the oldest `chat_count`
+/// chats (extended upto the next user prompt) are summarized by `summarizer` and replaced by
+/// a synthetic first exchange holding the summary. See `Session::compact`.
+///
+/// Keep `threshold` below the session's `history_limit`, else chats are popped before they
+/// can be summarized.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::ask::{Compaction, Gemini};
+/// let summarizer = Gemini::new("API_KEY", "gemini-2.5-flash-lite", None);
+/// let ai = Gemini::new("API_KEY", "gemini-2.5-flash", None)
+///     .set_compaction(Some(Compaction::new(summarizer, 40, 20).set_keep_archive(true)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compaction {
+    summarizer: Gemini,
+    threshold: usize,
+    chat_count: usize,
+    keep_archive: bool,
+    instruction: String,
+}
+impl Compaction {
+    pub fn new(summarizer: Gemini, threshold: usize, chat_count: usize) -> Self {
+        Self {
+            summarizer,
+            threshold,
+            chat_count,
+            keep_archive: false,
+            instruction: DEFAULT_INSTRUCTION.into(),
+        }
+    }
+    /// If true, summarized chats are kept in `Session::get_archive()`.
+    pub fn set_keep_archive(mut self, keep_archive: bool) -> Self {
+        self.keep_archive = keep_archive;
+        self
+    }
+    /// Instruction prepended to the conversation sent to `summarizer`.
+    pub fn set_instruction(mut self, instruction: impl Into<String>) -> Self {
+        self.instruction = instruction.into();
+        self
+    }
+    pub fn get_summarizer(&self) -> &Gemini {
+        &self.summarizer
+    }
+    pub fn get_threshold(&self) -> usize {
+        self.threshold
+    }
+    pub fn get_chat_count(&self) -> usize {
+        self.chat_count
+    }
+    pub fn get_keep_archive(&self) -> bool {
+        self.keep_archive
+    }
+    fn transcript<'a>(chats: impl Iterator<Item = &'a Chat>) -> String {
+        let mut transcript = String::new();
+        for chat in chats {
+            for part in chat.parts().iter().filter(|part| !part.is_thought()) {
+                let content = match part.data() {
+                    PartType::Text(text) => text.clone(),
+                    PartType::InlineData(inline_data) => {
+                        format!("[{} attachment]", inline_data.mime_type())
+                    }
+                    PartType::FileData(file_data) => format!("[file {}]", file_data.file_uri()),
+                    data => serde_json::to_string(data).unwrap_or_default(),
+                };
+                transcript.push_str(&format!("{:?}: {}\n", chat.role(), content));
+            }
+        }
+        transcript
+    }
+    /// Compacts `session` if its history has reached `threshold`.
+    /// # Returns
+    /// true if compacted.
+    pub async fn compact(&self, session: &mut Session) -> Result<bool, GeminiResponseError> {
+        if session.get_history_length() < self.threshold {
+            return Ok(false);
+        }
+        let Some(chat_count) = session.get_compactable_chat_count(self.chat_count) else {
+            return Ok(false);
+        };
+        let transcript =
+            Self::transcript(session.get_history_as_vecdeque().iter().take(chat_count));
+        let mut summary_session = Session::new(2);
+        summary_session.ask(format!("{}\n\n{}", self.instruction, transcript));
+        // Boxed as `ask` itself compacts.
+        let summary = Box::pin(self.summarizer.ask(&mut summary_session)).await?;
+        session.compact(
+            chat_count,
+            summary.get_chat().get_text_no_think("\n"),
+            self.keep_archive,
+        );
+        Ok(true)
+    }
+}
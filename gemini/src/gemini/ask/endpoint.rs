@@ -0,0 +1,102 @@
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    #[default]
+    V1Beta,
+}
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V1Beta => "v1beta",
+        }
+    }
+}
+
+/// Where requests of a `Gemini` client are sent.
+///
+/// Every request path, including caching, is built as
+/// `{base_url}/{path_prefix}/{api_version}/{path}`. Media uploads and downloads use
+/// `{base_url}/{path_prefix}/upload/{api_version}/{path}` and `.../download/...`.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::ask::{ApiVersion, Endpoint};
+/// // Requests go to http://localhost:8080/gemini/v1/models/...
+/// let endpoint = Endpoint::new("http://localhost:8080")
+///     .set_api_version(ApiVersion::V1)
+///     .set_path_prefix(Some("gemini"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    base_url: String,
+    api_version: ApiVersion,
+    path_prefix: Option<String>,
+}
+impl Endpoint {
+    pub const GOOGLE_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+
+    /// `base_url` is scheme and host, like "https://generativelanguage.googleapis.com"
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_version: ApiVersion::default(),
+            path_prefix: None,
+        }
+    }
+    pub fn set_api_version(mut self, api_version: ApiVersion) -> Self {
+        self.api_version = api_version;
+        self
+    }
+    /// Path inserted between the base URL and the API version.
+    pub fn set_path_prefix(mut self, path_prefix: Option<impl Into<String>>) -> Self {
+        self.path_prefix = path_prefix.map(|prefix| prefix.into());
+        self
+    }
+    pub fn get_base_url(&self) -> &str {
+        &self.base_url
+    }
+    pub fn get_api_version(&self) -> ApiVersion {
+        self.api_version
+    }
+    pub fn get_path_prefix(&self) -> Option<&str> {
+        self.path_prefix.as_deref()
+    }
+    /// Full URL of `path` like "models/gemini-2.5-flash:generateContent", without query.
+    pub fn url(&self, path: &str) -> String {
+        self.service_url(None, path)
+    }
+    /// Full URL of `path` for media uploads, like
+    /// "https://generativelanguage.googleapis.com/upload/v1beta/files".
+    pub fn upload_url(&self, path: &str) -> String {
+        self.service_url(Some("upload"), path)
+    }
+    /// Full URL of `path` for media downloads, like
+    /// "https://generativelanguage.googleapis.com/download/v1beta/files/abc:download".
+    pub fn download_url(&self, path: &str) -> String {
+        self.service_url(Some("download"), path)
+    }
+    fn service_url(&self, service: Option<&str>, path: &str) -> String {
+        let mut url = self.base_url.trim_end_matches('/').to_string();
+        if let Some(prefix) = self.path_prefix.as_deref().map(|p| p.trim_matches('/'))
+            && !prefix.is_empty()
+        {
+            url.push('/');
+            url.push_str(prefix);
+        }
+        if let Some(service) = service {
+            url.push('/');
+            url.push_str(service);
+        }
+        url.push('/');
+        url.push_str(self.api_version.as_str());
+        url.push('/');
+        url.push_str(path.trim_start_matches('/'));
+        url
+    }
+}
+impl Default for Endpoint {
+    fn default() -> Self {
+        Self::new(Self::GOOGLE_BASE_URL)
+    }
+}
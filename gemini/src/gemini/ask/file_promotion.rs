@@ -0,0 +1,207 @@
+use super::Gemini;
+use crate::gemini::error::GeminiResponseError;
+use crate::gemini::types::files::{File, FileState};
+use crate::gemini::types::request::{FileData, InlineData, PartType};
+use crate::gemini::types::sessions::Session;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Uploads are considered expired this long before the Files API deletes them, so that a
+/// request using one doesn't race its deletion.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+struct PromotedFile {
+    inline_data: InlineData,
+    uri: String,
+    expires: SystemTime,
+}
+
+/// Uploads large `InlineData` of sessions to the Files API and replaces them in history by
+/// `FileData`, so that they aren't sent as base64 on every `ask`.
+///
+/// Uploads are cached by content, so the same bytes are uploaded once. Uploaded files expire
+/// after 48 hours, so a `FileData` whose file expired is uploaded again from the cached bytes.
+/// Bytes of expired uploads are kept for `retention` for that, then evicted.
+///
+/// Cloning shares the cache. The cache lives in memory, so `FileData` of sessions restored in
+/// another process can't be uploaded again once expired.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::ask::{FilePromotion, Gemini};
+/// // Upload inline data of 1 MiB or more.
+/// let ai = Gemini::new("API_KEY", "gemini-2.5-flash", None)
+///     .set_file_promotion(Some(FilePromotion::new(1024 * 1024)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FilePromotion {
+    threshold: usize,
+    max_age: Duration,
+    retention: Duration,
+    processing_timeout: Duration,
+    cache: Arc<Mutex<HashMap<u64, PromotedFile>>>,
+}
+impl FilePromotion {
+    /// `threshold` is the minimum decoded size in bytes of `InlineData` to upload.
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            max_age: Duration::from_secs(47 * 60 * 60),
+            retention: Duration::from_secs(24 * 60 * 60),
+            processing_timeout: Duration::from_secs(5 * 60),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+    /// Age after which an upload is considered expired if the Files API doesn't tell its
+    /// expiration time. 47 hours by default.
+    pub fn set_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+    /// Time after expiry for which the bytes of an upload are kept, to upload them again for
+    /// sessions still referring to it. 24 hours by default.
+    pub fn set_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+    /// Time to wait for an uploaded file, like a video, to be processed. 5 minutes by default.
+    pub fn set_processing_timeout(mut self, processing_timeout: Duration) -> Self {
+        self.processing_timeout = processing_timeout;
+        self
+    }
+    pub fn get_threshold(&self) -> usize {
+        self.threshold
+    }
+    pub fn get_max_age(&self) -> Duration {
+        self.max_age
+    }
+    pub fn get_retention(&self) -> Duration {
+        self.retention
+    }
+    pub fn get_processing_timeout(&self) -> Duration {
+        self.processing_timeout
+    }
+    /// Count of uploads in the cache.
+    pub fn get_cached_count(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+    /// Forgets all uploads. Files are not deleted.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+    /// Forgets uploads expired for longer than `retention`. Called by `promote`.
+    pub fn evict_expired(&self) {
+        let now = SystemTime::now();
+        self.cache
+            .lock()
+            .unwrap()
+            .retain(|_, promoted| promoted.expires + self.retention > now);
+    }
+    /// Caches `file` as the upload of `inline_data`.
+    pub(crate) fn remember(&self, inline_data: InlineData, file: &File) -> String {
+        let uri = file.uri().clone().unwrap_or_default();
+        let expires = match file.get_expiration_time() {
+            Some(expiration_time) => expiration_time
+                .checked_sub(EXPIRY_MARGIN)
+                .unwrap_or(expiration_time),
+            None => SystemTime::now() + self.max_age,
+        };
+        self.cache.lock().unwrap().insert(
+            Self::key(&inline_data),
+            PromotedFile {
+                inline_data,
+                uri: uri.clone(),
+                expires,
+            },
+        );
+        uri
+    }
+    fn key(inline_data: &InlineData) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        inline_data.mime_type().as_ref().hash(&mut hasher);
+        inline_data.data().hash(&mut hasher);
+        hasher.finish()
+    }
+    fn is_large(&self, inline_data: &InlineData) -> bool {
+        // 4 base64 characters encode 3 bytes.
+        inline_data.data().len() / 4 * 3 >= self.threshold
+    }
+    /// Cached URI of an upload of `inline_data` if it isn't expired. The cached bytes are
+    /// compared too, as different data may hash to the same key.
+    fn cached_uri(&self, inline_data: &InlineData) -> Option<String> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(&Self::key(inline_data))
+            .filter(|promoted| {
+                promoted.inline_data.mime_type() == inline_data.mime_type()
+                    && promoted.inline_data.data() == inline_data.data()
+                    && promoted.expires > SystemTime::now()
+            })
+            .map(|promoted| promoted.uri.clone())
+    }
+    /// Original data of an expired upload of `uri`.
+    fn expired(&self, uri: &str) -> Option<InlineData> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .values()
+            .find(|promoted| promoted.uri == uri)
+            .filter(|promoted| promoted.expires <= SystemTime::now())
+            .map(|promoted| promoted.inline_data.clone())
+    }
+    async fn upload(
+        &self,
+        gemini: &Gemini,
+        inline_data: InlineData,
+    ) -> Result<String, GeminiResponseError> {
+        let data = STANDARD
+            .decode(inline_data.data())
+            .map_err(GeminiResponseError::Base64DecodeError)?;
+        let mut file = gemini
+            .upload_file(data, inline_data.mime_type().clone(), None)
+            .await?;
+        if file.state() == &Some(FileState::Processing) {
+            file = gemini
+                .wait_for_file_active(file.name(), Duration::from_secs(2), self.processing_timeout)
+                .await?;
+        }
+        Ok(self.remember(inline_data, &file))
+    }
+    /// Uploads large `InlineData` of `session` (or uses cached uploads) and replaces them by
+    /// `FileData`. Expired uploads made by this policy are uploaded again, then uploads expired
+    /// for longer than `retention` are evicted.
+    pub async fn promote(
+        &self,
+        gemini: &Gemini,
+        session: &mut Session,
+    ) -> Result<(), GeminiResponseError> {
+        for chat in session.get_history_as_vecdeque_mut().iter_mut() {
+            for part in chat.parts_mut() {
+                let file_data = match part.data() {
+                    PartType::InlineData(inline_data) if self.is_large(inline_data) => {
+                        let uri = match self.cached_uri(inline_data) {
+                            Some(uri) => uri,
+                            None => self.upload(gemini, inline_data.clone()).await?,
+                        };
+                        FileData::new(Some(inline_data.mime_type().to_string()), uri)
+                    }
+                    PartType::FileData(file_data) => match self.expired(file_data.file_uri()) {
+                        Some(inline_data) => {
+                            let mime_type = Some(inline_data.mime_type().to_string());
+                            FileData::new(mime_type, self.upload(gemini, inline_data).await?)
+                        }
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                *part.data_mut() = PartType::FileData(file_data);
+            }
+        }
+        self.evict_expired();
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use super::Gemini;
+use crate::gemini::error::GeminiResponseError;
+use crate::gemini::types::files::{File, FileList, FileResponse, FileState};
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use mime::Mime;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+/// Size of each chunk sent by `upload_file_stream` unless Gemini asks for a multiple of other size.
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+impl Gemini {
+    /// Starts a resumable upload and returns the URL to send the bytes to and the chunk
+    /// granularity.
+    async fn start_upload(
+        &self,
+        size: u64,
+        mime_type: &Mime,
+        display_name: Option<&str>,
+    ) -> Result<(String, Option<usize>), GeminiResponseError> {
+        let req_url = format!("{}?key={}", self.endpoint.upload_url("files"), self.api_key);
+        let body = match display_name {
+            Some(display_name) => json!({"file": {"displayName": display_name}}),
+            None => json!({"file": {}}),
+        };
+        let response = self
+            .send_non_idempotent(|| {
+                self.client
+                    .post(&req_url)
+                    .header("X-Goog-Upload-Protocol", "resumable")
+                    .header("X-Goog-Upload-Command", "start")
+                    .header("X-Goog-Upload-Header-Content-Length", size)
+                    .header("X-Goog-Upload-Header-Content-Type", mime_type.as_ref())
+                    .json(&body)
+            })
+            .await?;
+        let headers = response.headers();
+        let upload_url = headers
+            .get("x-goog-upload-url")
+            .and_then(|url| url.to_str().ok())
+            .ok_or(GeminiResponseError::UploadUrlMissing)?
+            .to_string();
+        let granularity = headers
+            .get("x-goog-upload-chunk-granularity")
+            .and_then(|granularity| granularity.to_str().ok()?.parse().ok());
+        Ok((upload_url, granularity))
+    }
+    async fn upload_chunk(
+        &self,
+        upload_url: &str,
+        chunk: Bytes,
+        offset: u64,
+        last: bool,
+    ) -> Result<Option<File>, GeminiResponseError> {
+        let command = if last { "upload, finalize" } else { "upload" };
+        let response = self
+            .send(|| {
+                self.client
+                    .post(upload_url)
+                    .header("X-Goog-Upload-Offset", offset)
+                    .header("X-Goog-Upload-Command", command)
+                    .body(chunk.clone())
+            })
+            .await?;
+        if !last {
+            return Ok(None);
+        }
+        let response: FileResponse = response
+            .json()
+            .await
+            .map_err(GeminiResponseError::ReqwestError)?;
+        Ok(Some(response.file))
+    }
+
+    /// Uploads `data` to the [Files API](https://ai.google.dev/api/files) by the resumable
+    /// protocol. Uploaded file can be used in prompts by `File::to_file_data` once active,
+    /// see `wait_for_file_active`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini) {
+    /// let pdf = std::fs::read("report.pdf").unwrap();
+    /// let file = gemini
+    ///     .upload_file(pdf, mime::APPLICATION_PDF, Some("report"))
+    ///     .await
+    ///     .unwrap();
+    /// println!("{:?}", file.uri());
+    /// # }
+    /// ```
+    pub async fn upload_file(
+        &self,
+        data: impl Into<Bytes>,
+        mime_type: Mime,
+        display_name: Option<&str>,
+    ) -> Result<File, GeminiResponseError> {
+        let data = data.into();
+        let (upload_url, _) = self
+            .start_upload(data.len() as u64, &mime_type, display_name)
+            .await?;
+        let file = self.upload_chunk(&upload_url, data, 0, true).await?;
+        Ok(file.expect("finalized upload returns the file"))
+    }
+    /// Same as `upload_file` but reads the data from `stream` of total `size` bytes, sending
+    /// it in chunks so that the whole file is never in memory.
+    pub async fn upload_file_stream<S, E>(
+        &self,
+        stream: S,
+        size: u64,
+        mime_type: Mime,
+        display_name: Option<&str>,
+    ) -> Result<File, GeminiResponseError>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+        E: Into<std::io::Error>,
+    {
+        let (upload_url, granularity) = self.start_upload(size, &mime_type, display_name).await?;
+        let chunk_size = match granularity {
+            Some(granularity) if granularity > 0 => CHUNK_SIZE.div_ceil(granularity) * granularity,
+            _ => CHUNK_SIZE,
+        };
+        let mut stream = std::pin::pin!(stream);
+        let mut buffer = BytesMut::new();
+        let mut offset = 0;
+        loop {
+            let next = stream.next().await;
+            if let Some(bytes) = next {
+                let bytes = bytes.map_err(|error| GeminiResponseError::IoError(error.into()))?;
+                buffer.extend_from_slice(&bytes);
+                while buffer.len() > chunk_size {
+                    let chunk = buffer.split_to(chunk_size).freeze();
+                    let chunk_len = chunk.len() as u64;
+                    self.upload_chunk(&upload_url, chunk, offset, false).await?;
+                    offset += chunk_len;
+                }
+            } else {
+                let file = self
+                    .upload_chunk(&upload_url, buffer.freeze(), offset, true)
+                    .await?;
+                return Ok(file.expect("finalized upload returns the file"));
+            }
+        }
+    }
+    /// Uploads the file at `path` in chunks. See `upload_file_stream`.
+    /// `display_name` defaults to the file name.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub async fn upload_file_from_path(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        mime_type: Mime,
+        display_name: Option<&str>,
+    ) -> Result<File, GeminiResponseError> {
+        use tokio::io::AsyncReadExt;
+        let path = path.as_ref();
+        let file = tokio::fs::File::open(path)
+            .await
+            .map_err(GeminiResponseError::IoError)?;
+        let size = file
+            .metadata()
+            .await
+            .map_err(GeminiResponseError::IoError)?
+            .len();
+        let stream = futures::stream::try_unfold(file, |mut file| async move {
+            let mut buffer = BytesMut::with_capacity(CHUNK_SIZE);
+            let read = file.read_buf(&mut buffer).await?;
+            Ok::<_, std::io::Error>((read != 0).then(|| (buffer.freeze(), file)))
+        });
+        let display_name = display_name
+            .map(String::from)
+            .or_else(|| path.file_name().map(|name| name.to_string_lossy().into()));
+        self.upload_file_stream(stream, size, mime_type, display_name.as_deref())
+            .await
+    }
+
+    /// Gets the metadata of file `name` like "files/abc-123".
+    pub async fn get_file(&self, name: &str) -> Result<File, GeminiResponseError> {
+        let req_url = self.url(name);
+
+        let response = self.send(|| self.client.get(&req_url)).await?;
+
+        let file: File = response
+            .json()
+            .await
+            .map_err(GeminiResponseError::ReqwestError)?;
+        Ok(file)
+    }
+    /// Lists files uploaded by this project.
+    /// # Arguments
+    /// * `page_size` - Maximum files per page, 10 by default and upto 100.
+    /// * `page_token` - `FileList::next_page_token` of the previous page.
+    pub async fn list_files(
+        &self,
+        page_size: Option<u32>,
+        page_token: Option<&str>,
+    ) -> Result<FileList, GeminiResponseError> {
+        let req_url = self.url_with_query(
+            "files",
+            [
+                ("pageSize", page_size.map(|size| size.to_string())),
+                ("pageToken", page_token.map(str::to_string)),
+            ],
+        );
+
+        let response = self.send(|| self.client.get(&req_url)).await?;
+
+        let list: FileList = response
+            .json()
+            .await
+            .map_err(GeminiResponseError::ReqwestError)?;
+        Ok(list)
+    }
+    pub async fn delete_file(&self, name: &str) -> Result<(), GeminiResponseError> {
+        let req_url = self.url(name);
+
+        self.send(|| self.client.delete(&req_url)).await?;
+
+        Ok(())
+    }
+    /// Polls file `name` every `poll_interval` until it is `FileState::Active`.
+    /// Videos and large documents are processed for a while after upload.
+    ///
+    /// # Errors
+    /// * `FileProcessingFailed` if processing failed.
+    /// * `FileProcessingTimeout` if still processing after `timeout`.
+    pub async fn wait_for_file_active(
+        &self,
+        name: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<File, GeminiResponseError> {
+        let start = Instant::now();
+        loop {
+            let file = self.get_file(name).await?;
+            match file.state() {
+                Some(FileState::Failed) => {
+                    return Err(GeminiResponseError::FileProcessingFailed(Box::new(file)));
+                }
+                Some(FileState::Processing) => {
+                    if start.elapsed() + poll_interval > timeout {
+                        return Err(GeminiResponseError::FileProcessingTimeout(Box::new(file)));
+                    }
+                    tokio::time::sleep(poll_interval).await;
+                }
+                _ => return Ok(file),
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+struct Window {
+    requests: VecDeque<Instant>,
+    tokens: VecDeque<(Instant, u64)>,
+    token_sum: u64,
+}
+impl Window {
+    fn prune(&mut self, now: Instant) {
+        while self
+            .requests
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= WINDOW)
+        {
+            self.requests.pop_front();
+        }
+        while let Some((spent, tokens)) = self.tokens.front()
+            && now.duration_since(*spent) >= WINDOW
+        {
+            self.token_sum -= tokens;
+            self.tokens.pop_front();
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Limits {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u64>,
+    in_flight: Option<Arc<Semaphore>>,
+    window: Mutex<Window>,
+}
+
+/// Client side limiter for requests per minute, tokens per minute and requests in flight.
+///
+/// Requests exceeding a limit wait asynchronously instead of failing with
+/// `Status::ResourceExhausted`. Token spend is known only after a reply, so it is taken from
+/// `usage_metadata` of each `GeminiResponse` and limits the requests after it.
+///
+/// Cloning shares the limits, so one `RateLimiter` given to many (cloned) `Gemini` clients
+/// governs their combined usage of an API key.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::ask::{Gemini, RateLimiter};
+/// let limiter = RateLimiter::new(Some(15), Some(250_000), Some(4));
+/// let ai = Gemini::new("API_KEY", "gemini-2.5-flash", None).set_rate_limiter(Some(limiter));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limits: Arc<Limits>,
+}
+impl RateLimiter {
+    /// `None` means no limit.
+    pub fn new(
+        requests_per_minute: Option<u32>,
+        tokens_per_minute: Option<u64>,
+        max_in_flight: Option<usize>,
+    ) -> Self {
+        Self {
+            limits: Arc::new(Limits {
+                requests_per_minute,
+                tokens_per_minute,
+                in_flight: max_in_flight.map(|max| Arc::new(Semaphore::new(max))),
+                window: Mutex::new(Window::default()),
+            }),
+        }
+    }
+    pub fn get_requests_per_minute(&self) -> Option<u32> {
+        self.limits.requests_per_minute
+    }
+    pub fn get_tokens_per_minute(&self) -> Option<u64> {
+        self.limits.tokens_per_minute
+    }
+    /// Tokens recorded in the last minute.
+    pub fn get_tokens_spent(&self) -> u64 {
+        let mut window = self.limits.window.lock().unwrap();
+        window.prune(Instant::now());
+        window.token_sum
+    }
+    /// Waits until a request can be sent without exceeding any limit and counts it as sent.
+    /// The request is in flight until the returned permit is dropped.
+    pub async fn acquire(&self) -> RateLimitPermit {
+        let in_flight = match &self.limits.in_flight {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore is never closed"),
+            ),
+            None => None,
+        };
+        loop {
+            let wait = {
+                let mut window = self.limits.window.lock().unwrap();
+                let now = Instant::now();
+                window.prune(now);
+                let request_wait = self
+                    .limits
+                    .requests_per_minute
+                    .filter(|limit| window.requests.len() >= *limit as usize)
+                    .and_then(|_| window.requests.front())
+                    .map(|sent| WINDOW - now.duration_since(*sent));
+                let token_wait = self
+                    .limits
+                    .tokens_per_minute
+                    .filter(|limit| window.token_sum >= *limit)
+                    .and_then(|_| window.tokens.front())
+                    .map(|(spent, _)| WINDOW - now.duration_since(*spent));
+                match request_wait.max(token_wait) {
+                    Some(wait) => wait,
+                    None => {
+                        window.requests.push_back(now);
+                        break;
+                    }
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+        RateLimitPermit {
+            limiter: self.clone(),
+            pending_tokens: 0,
+            _in_flight: in_flight,
+        }
+    }
+    /// Counts `tokens` as spent now.
+    pub fn record_tokens(&self, tokens: u64) {
+        if tokens == 0 {
+            return;
+        }
+        let mut window = self.limits.window.lock().unwrap();
+        window.tokens.push_back((Instant::now(), tokens));
+        window.token_sum += tokens;
+    }
+}
+
+/// Marks a request in flight until dropped.
+#[derive(Debug)]
+pub struct RateLimitPermit {
+    limiter: RateLimiter,
+    pending_tokens: u64,
+    _in_flight: Option<OwnedSemaphorePermit>,
+}
+impl RateLimitPermit {
+    pub fn record_tokens(&self, tokens: u64) {
+        self.limiter.record_tokens(tokens);
+    }
+    /// Tokens spent so far by the request, recorded when the permit is dropped. Lets a reply
+    /// dropped midway, like an unfinished stream, still count its usage.
+    pub fn set_pending_tokens(&mut self, tokens: u64) {
+        self.pending_tokens = tokens;
+    }
+}
+impl Drop for RateLimitPermit {
+    fn drop(&mut self) {
+        self.limiter.record_tokens(self.pending_tokens);
+    }
+}
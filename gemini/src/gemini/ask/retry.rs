@@ -0,0 +1,132 @@
+use crate::gemini::error::{Error, Status};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Decides if and after how long a request failed with `GeminiResponseError::StatusNotOk`
+/// is sent again.
+///
+/// The delay is exponential: `base_delay * 2^(attempt - 1)` capped at `max_delay`, but the
+/// `RetryInfo` delay sent by Gemini in `Error::details` is used whenever present, capped at
+/// `max_delay` too.
+///
+/// Requests creating something, like `create_cache`, `create_batch` and the start of an
+/// upload, aren't idempotent: a failure after Gemini accepted one can't be told apart, so
+/// resending it could create a duplicate. They are retried only on `ResourceExhausted`, which
+/// rejects a request before it is processed, unless `set_retry_non_idempotent(true)`.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::ask::RetryPolicy;
+/// use std::time::Duration;
+/// let policy = RetryPolicy::default()
+///     .set_max_attempts(5)
+///     .set_base_delay(Duration::from_millis(500));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retryable: Vec<Status>,
+    retry_non_idempotent: bool,
+}
+impl RetryPolicy {
+    /// Total attempts including the first one. `1` means never retry.
+    pub fn set_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+    pub fn set_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+    pub fn set_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+    /// If true, exponential delays are randomly reduced by upto half so that clients
+    /// failing together don't retry together.
+    pub fn set_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+    pub fn set_retryable(mut self, retryable: Vec<Status>) -> Self {
+        self.retryable = retryable;
+        self
+    }
+    /// If true, requests creating something are retried on every retryable status too. False
+    /// by default.
+    pub fn set_retry_non_idempotent(mut self, retry_non_idempotent: bool) -> Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+    pub fn get_retry_non_idempotent(&self) -> bool {
+        self.retry_non_idempotent
+    }
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+    pub fn get_base_delay(&self) -> Duration {
+        self.base_delay
+    }
+    pub fn get_max_delay(&self) -> Duration {
+        self.max_delay
+    }
+    pub fn get_jitter(&self) -> bool {
+        self.jitter
+    }
+    pub fn get_retryable(&self) -> &[Status] {
+        &self.retryable
+    }
+    pub fn is_retryable(&self, status: &Status) -> bool {
+        self.retryable.contains(status)
+    }
+    /// Delay before the next attempt after `attempt` (starting from 1) failed with `error`.
+    /// Returns `None` if it must not be retried.
+    /// `delay` of a request creating something.
+    pub(crate) fn delay_non_idempotent(&self, attempt: u32, error: &Error) -> Option<Duration> {
+        if !self.retry_non_idempotent && error.status != Status::ResourceExhausted {
+            return None;
+        }
+        self.delay(attempt, error)
+    }
+    pub(crate) fn delay(&self, attempt: u32, error: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.is_retryable(&error.status) {
+            return None;
+        }
+        if let Some(delay) = error.retry_delay() {
+            return Some(delay.min(self.max_delay));
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        if self.jitter {
+            let random = RandomState::new().build_hasher().finish();
+            let fraction = (random % 1000) as f64 / 2000.0;
+            Some(backoff.mul_f64(1.0 - fraction))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+impl Default for RetryPolicy {
+    /// 4 attempts, starting from 1s delay upto 60s, with jitter, retrying `ResourceExhausted`,
+    /// `Unavailable` and `Internal`.
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: true,
+            retryable: vec![
+                Status::ResourceExhausted,
+                Status::Unavailable,
+                Status::Internal,
+            ],
+            retry_non_idempotent: false,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use super::Gemini;
+use crate::gemini::error::GeminiResponseError;
+use crate::gemini::types::request::{Chat, PartType};
+use crate::gemini::types::sessions::{HeuristicTokenCounter, Session, TokenCounter};
+use futures::{StreamExt, TryStreamExt, stream};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// countTokens requests sent at once by `ApiTokenCounter::measure`.
+const MAX_CONCURRENT_COUNTS: usize = 4;
+
+#[derive(Debug, Default)]
+struct Measured {
+    counts: HashMap<u64, u32>,
+    /// Keys of `counts`, oldest first.
+    order: VecDeque<u64>,
+}
+
+/// `TokenCounter` backed by Gemini's countTokens API.
+///
+/// `TokenCounter::count_tokens` can't make requests, so it answers from counts measured
+/// earlier by `measure` and falls back to `HeuristicTokenCounter` for chats never measured.
+/// Clones share the measured counts, of which the oldest are forgotten past `get_capacity`.
+///
+/// # Example
+/// ```no_run
+/// use gemini_client_api::gemini::ask::{ApiTokenCounter, Gemini};
+/// use gemini_client_api::gemini::types::sessions::Session;
+/// # async fn run() {
+/// let ai = Gemini::new("API_KEY", "gemini-2.5-flash", None);
+/// let counter = ApiTokenCounter::new(ai.clone());
+/// let mut session = Session::new(100)
+///     .set_token_limit(Some(100_000))
+///     .set_token_counter(counter.clone());
+/// ai.ask(session.ask("Hi")).await.unwrap();
+/// // Counts of the chats in history are exact from here on.
+/// counter.measure(&session).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ApiTokenCounter {
+    gemini: Gemini,
+    fallback: HeuristicTokenCounter,
+    capacity: usize,
+    measured: Arc<Mutex<Measured>>,
+}
+impl ApiTokenCounter {
+    /// `gemini` is used only for its model, API key and endpoint.
+    pub fn new(gemini: Gemini) -> Self {
+        Self {
+            gemini,
+            fallback: HeuristicTokenCounter::default(),
+            capacity: 1024,
+            measured: Arc::new(Mutex::new(Measured::default())),
+        }
+    }
+    pub fn set_fallback(mut self, fallback: HeuristicTokenCounter) -> Self {
+        self.fallback = fallback;
+        self
+    }
+    /// Maximum counts remembered, 1024 by default. Make it at least the history limit of the
+    /// sessions measured.
+    pub fn set_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Inline data is keyed by its MIME type and size only, which its tokens depend on, so that
+    /// large media isn't hashed on every count.
+    fn key(chat: &Chat) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        std::mem::discriminant(chat.role()).hash(&mut hasher);
+        for part in chat.parts() {
+            part.is_thought().hash(&mut hasher);
+            match part.data() {
+                PartType::Text(text) => text.hash(&mut hasher),
+                PartType::InlineData(inline_data) => {
+                    inline_data.mime_type().as_ref().hash(&mut hasher);
+                    inline_data.data().len().hash(&mut hasher);
+                }
+                PartType::FileData(file_data) => {
+                    file_data.mime_type().hash(&mut hasher);
+                    file_data.file_uri().hash(&mut hasher);
+                }
+                data => serde_json::to_string(data)
+                    .unwrap_or_default()
+                    .hash(&mut hasher),
+            }
+        }
+        hasher.finish()
+    }
+    /// Measures tokens of every chat in the history of `session` not measured yet, by one
+    /// countTokens request per chat. Upto 4 requests are sent at once, each waiting for the
+    /// `RateLimiter` of the client if set.
+    pub async fn measure(&self, session: &Session) -> Result<(), GeminiResponseError> {
+        let unmeasured: Vec<(u64, &Chat)> = {
+            let measured = self.measured.lock().unwrap();
+            let mut seen = HashSet::new();
+            session
+                .get_history_as_vecdeque()
+                .iter()
+                .map(|chat| (Self::key(chat), chat))
+                .filter(|(key, _)| !measured.counts.contains_key(key) && seen.insert(*key))
+                .collect()
+        };
+        let counts: Vec<(u64, u32)> = stream::iter(unmeasured)
+            .map(async |(key, chat)| {
+                self.gemini
+                    .count_contents_tokens_inner(&[chat], true)
+                    .await
+                    .map(|count| (key, count.total_tokens))
+            })
+            .buffer_unordered(MAX_CONCURRENT_COUNTS)
+            .try_collect()
+            .await?;
+        let mut measured = self.measured.lock().unwrap();
+        for (key, tokens) in counts {
+            if measured.counts.insert(key, tokens).is_none() {
+                measured.order.push_back(key);
+            }
+        }
+        while measured.order.len() > self.capacity {
+            if let Some(key) = measured.order.pop_front() {
+                measured.counts.remove(&key);
+            }
+        }
+        Ok(())
+    }
+    /// Count of measured chats remembered.
+    pub fn len(&self) -> usize {
+        self.measured.lock().unwrap().counts.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Forgets all measured counts.
+    pub fn clear(&self) {
+        let mut measured = self.measured.lock().unwrap();
+        measured.counts.clear();
+        measured.order.clear();
+    }
+}
+impl TokenCounter for ApiTokenCounter {
+    fn count_tokens(&self, chat: &Chat) -> u32 {
+        match self.measured.lock().unwrap().counts.get(&Self::key(chat)) {
+            Some(tokens) => *tokens,
+            None => self.fallback.count_tokens(chat),
+        }
+    }
+}
@@ -1,9 +1,16 @@
+use super::ask::AgentRun;
+use super::types::batch::BatchJob;
+use super::types::files::File;
+use super::types::response::GeminiResponse;
+use super::types::sessions::AddFunctionResponseError;
 use reqwest::StatusCode;
 use serde::{Deserialize, Deserializer};
 use serde_json::Value;
+use std::time::Duration;
 
-#[derive(Deserialize, thiserror::Error, Debug, PartialEq, Eq)]
+#[derive(Deserialize, thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
 pub enum Status {
     #[error("The request body is malformed.")]
     ///The request body is malformed.
@@ -31,6 +38,25 @@ pub enum Status {
     #[error("The service is unable to finish processing within the deadline.")]
     ///The service is unable to finish processing within the deadline.
     DeadlineExceeded,
+    #[error("An unrecognized error occurred.")]
+    ///Status not listed here, or not sent by Gemini.
+    #[serde(other)]
+    Unknown,
+}
+impl Status {
+    /// Status matching an HTTP status code, for errors without a body from Gemini.
+    pub fn from_status_code(code: StatusCode) -> Self {
+        match code {
+            StatusCode::BAD_REQUEST => Self::InvalidArgument,
+            StatusCode::FORBIDDEN => Self::PermissionDenied,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::TOO_MANY_REQUESTS => Self::ResourceExhausted,
+            StatusCode::INTERNAL_SERVER_ERROR => Self::Internal,
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => Self::Unavailable,
+            StatusCode::GATEWAY_TIMEOUT => Self::DeadlineExceeded,
+            _ => Self::Unknown,
+        }
+    }
 }
 fn deserialize_status_code<'de, D>(deserializer: D) -> Result<StatusCode, D::Error>
 where
@@ -48,12 +74,38 @@ pub struct Error {
     pub status: Status,
     pub details: Option<Vec<Value>>,
 }
+impl Error {
+    /// Delay asked by Gemini in `google.rpc.RetryInfo` of `details` before retrying.
+    pub fn retry_delay(&self) -> Option<Duration> {
+        self.details.as_ref()?.iter().find_map(|detail| {
+            if !detail["@type"].as_str()?.ends_with("google.rpc.RetryInfo") {
+                return None;
+            }
+            let seconds = detail["retryDelay"].as_str()?.strip_suffix('s')?;
+            Duration::try_from_secs_f64(seconds.parse().ok()?).ok()
+        })
+    }
+}
 
 #[derive(Deserialize, thiserror::Error, Debug)]
 #[error("Gemini API Error: {error}")]
 pub struct GeminiError {
     pub error: Error,
 }
+impl GeminiError {
+    /// Parses the body of a failed response. A body not sent by Gemini, like an HTML page of a
+    /// proxy, becomes the message with the status taken from `code`.
+    pub fn from_body(code: StatusCode, body: &str) -> Self {
+        serde_json::from_str(body).unwrap_or_else(|_| Self {
+            error: Error {
+                code,
+                message: body.to_string(),
+                status: Status::from_status_code(code),
+                details: None,
+            },
+        })
+    }
+}
 #[derive(thiserror::Error, Debug)]
 pub enum GeminiResponseError {
     #[error(transparent)]
@@ -63,6 +115,36 @@ pub enum GeminiResponseError {
     #[error("Cannot Respond if last Chat has Role::Model")]
     ///Cannot Respond if last Chat has Role::Model
     NothingToRespond,
+    #[error(transparent)]
+    IoError(std::io::Error),
+    #[error(transparent)]
+    Base64DecodeError(base64::DecodeError),
+    #[error(transparent)]
+    SerdeJsonError(serde_json::Error),
+    #[error("Upload URL was missing in response headers")]
+    ///Upload URL was missing in response headers
+    UploadUrlMissing,
+    #[error("File processing failed: {0:?}")]
+    ///File reached `FileState::Failed`. Contains the file
+    FileProcessingFailed(Box<File>),
+    #[error("File didn't become active in time: {0:?}")]
+    ///File was still processing when waiting timed out. Contains the file
+    FileProcessingTimeout(Box<File>),
+    #[error("Batch wasn't done in time: {0:?}")]
+    ///Batch was still pending or running when waiting timed out. Contains the batch
+    BatchTimeout(Box<BatchJob>),
+    #[error("Reply didn't match the schema: {0}")]
+    ///Reply couldn't be deserialized to the structured type. Contains the error and the reply
+    InvalidStructuredOutput(serde_json::Error, Box<GeminiResponse>),
+    #[error("Response was blocked. Prompt feedback: {:?}", .0.prompt_feedback)]
+    ///Prompt or every candidate was blocked. Contains the response with `prompt_feedback` and
+    ///`safety_ratings` of candidates
+    Blocked(Box<GeminiResponse>),
+    #[error(transparent)]
+    AddFunctionResponseError(AddFunctionResponseError),
+    #[error("Model was still calling functions after {} replies", .0.iterations)]
+    ///Agent reached the maximum iterations. Contains the run so far
+    MaxIterationsReached(Box<AgentRun>),
 }
 
 #[derive(thiserror::Error, Debug)]
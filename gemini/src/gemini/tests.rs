@@ -1,4 +1,16 @@
 mod ask;
+mod batch;
 mod caching_tests;
+mod embedding;
+mod endpoint;
 mod error;
+mod file_promotion;
+mod files;
+mod models;
+mod partial_json;
+mod rate_limit;
+mod request;
+mod response;
+mod retry;
+mod sessions;
 mod utils;
@@ -1,8 +1,11 @@
-use crate::gemini::ask::Gemini;
-use crate::gemini::types::request::{ThinkingConfig, Tool};
+use crate::gemini::ask::{FilePromotion, Gemini};
+use crate::gemini::types::request::{InlineData, PartType, SpeechConfig, ThinkingConfig, Tool};
+use crate::gemini::types::response::Modality;
 use crate::gemini::types::sessions::Session;
+use base64::{Engine, engine::general_purpose::STANDARD};
 use futures::StreamExt;
 use serde_json::{Value, json};
+use std::time::Duration;
 
 #[tokio::test]
 async fn ask_string() {
@@ -104,3 +107,178 @@ async fn thinking_test() {
     assert!(response.get_chat().get_text_no_think("").len() > 1);
     assert!(response.get_chat().get_thoughts("").len() > 1);
 }
+
+#[tokio::test]
+async fn count_tokens_test() {
+    let mut session = Session::new(4);
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash",
+        Some("Reply in one word".into()),
+    );
+    let tokens = ai.count_tokens(session.ask("Hi")).await.unwrap();
+    assert!(tokens.total_tokens > 0);
+}
+
+#[tokio::test]
+async fn embed_test() {
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-embedding-001",
+        None,
+    );
+    let embedding = ai.embed("Hi").await.unwrap();
+    assert!(!embedding.values.is_empty());
+    let embeddings = ai
+        .embed_batch(vec!["Hi".into(), "Hello".into()])
+        .await
+        .unwrap();
+    assert_eq!(embeddings.len(), 2);
+}
+
+#[tokio::test]
+async fn files_test() {
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash",
+        None,
+    );
+    let file = ai
+        .upload_file(
+            "Rust was first released in 2015.",
+            mime::TEXT_PLAIN,
+            Some("fact"),
+        )
+        .await
+        .unwrap();
+    let file = ai
+        .wait_for_file_active(file.name(), Duration::from_secs(2), Duration::from_secs(60))
+        .await
+        .unwrap();
+    let mut session = Session::new(2);
+    session.ask_parts(vec![
+        file.to_file_data().into(),
+        "When was Rust first released?".into(),
+    ]);
+    let response = ai.ask(&mut session).await.unwrap();
+    assert!(response.get_chat().get_text_no_think("").contains("2015"));
+    ai.delete_file(file.name()).await.unwrap();
+}
+
+#[tokio::test]
+async fn file_promotion_test() {
+    let promotion = FilePromotion::new(100);
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash",
+        None,
+    )
+    .set_file_promotion(Some(promotion.clone()));
+    let text = "Rust was first released in 2015. ".repeat(10);
+    let mut session = Session::new(4);
+    session.ask_parts(vec![
+        InlineData::new(mime::TEXT_PLAIN, STANDARD.encode(text)).into(),
+        "When was Rust first released?".into(),
+    ]);
+    let response = ai.ask(&mut session).await.unwrap();
+    assert!(response.get_chat().get_text_no_think("").contains("2015"));
+    assert!(matches!(
+        session.get_history()[0].parts()[0].data(),
+        PartType::FileData(_)
+    ));
+    assert_eq!(promotion.get_cached_count(), 1);
+}
+
+#[tokio::test]
+async fn models_test() {
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash",
+        None,
+    );
+    let model = ai.get_model_info().await.unwrap();
+    assert!(model.supports("generateContent"));
+    let models: Vec<_> = ai
+        .list_models(Some(10))
+        .take(15)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(models.len(), 15);
+}
+
+#[tokio::test]
+async fn batch_test() {
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash",
+        None,
+    );
+    let requests = vec![
+        ai.batch_request("one", Session::new(2).ask("Reply with just the number 1")),
+        ai.batch_request("two", Session::new(2).ask("Reply with just the number 2")),
+    ];
+    let job = ai
+        .create_batch_from_file("batch_test", &requests)
+        .await
+        .unwrap();
+    let job = ai
+        .wait_for_batch(
+            job.name(),
+            Duration::from_secs(30),
+            Some(Duration::from_secs(1800)),
+        )
+        .await
+        .unwrap();
+    let results: Vec<_> = Box::pin(ai.get_batch_results(&job).await.unwrap())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(results.len(), 2);
+    ai.delete_batch(job.name()).await.unwrap();
+}
+
+#[tokio::test]
+async fn image_generation_test() {
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash-image",
+        None,
+    )
+    .set_response_modalities(vec![Modality::Text, Modality::Image]);
+    let mut session = Session::new(6);
+    session.ask("Draw a minimal red circle on white background");
+    let response = ai.ask(&mut session).await.unwrap();
+    assert_eq!(response.get_chat().get_images().count(), 1);
+    session.ask("Make the circle blue");
+    let response = ai.ask(&mut session).await.unwrap();
+    let paths = response
+        .get_chat()
+        .save_images(std::env::temp_dir().join("blue_circle").to_string_lossy())
+        .await
+        .unwrap();
+    assert_eq!(paths.len(), 1);
+}
+
+#[tokio::test]
+async fn speech_generation_test() {
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash-preview-tts",
+        None,
+    )
+    .set_speech_config(SpeechConfig::new_multi_speaker([
+        ("Joe", "Kore"),
+        ("Jane", "Puck"),
+    ]));
+    let mut session = Session::new(2).set_remember_reply(false);
+    session.ask("TTS the following conversation:\nJoe: How's it going?\nJane: Not too bad!");
+    let response = ai.ask(&mut session).await.unwrap();
+    let audio = response.get_chat().get_audios().next().unwrap();
+    let path = audio
+        .save_wav(std::env::temp_dir().join("conversation"))
+        .await
+        .unwrap();
+    assert!(std::fs::metadata(path).unwrap().len() > 44);
+}
@@ -0,0 +1,80 @@
+use crate::gemini::ask::Gemini;
+use crate::gemini::types::batch::{BatchJob, BatchOperation, BatchResult, BatchState};
+use crate::gemini::types::sessions::Session;
+use serde_json::json;
+
+#[test]
+fn batch_operation_deserialization() {
+    let operation: BatchOperation = serde_json::from_value(json!({
+        "name": "batches/abc",
+        "metadata": {
+            "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.GenerateContentBatch",
+            "model": "models/gemini-2.5-flash",
+            "displayName": "nightly",
+            "state": "JOB_STATE_SUCCEEDED",
+            "batchStats": {"requestCount": "2", "successfulRequestCount": "1", "failedRequestCount": "1"},
+            "output": {"inlinedResponses": {"inlinedResponses": [
+                {
+                    "response": {
+                        "candidates": [{"content": {"role": "model", "parts": [{"text": "Hi"}]}}],
+                        "modelVersion": "gemini-2.5-flash"
+                    },
+                    "metadata": {"key": "first"}
+                },
+                {"error": {"code": 3, "message": "bad"}, "metadata": {"key": "second"}}
+            ]}}
+        }
+    }))
+    .unwrap();
+    let job: BatchJob = operation.into();
+    assert_eq!(job.name(), "batches/abc");
+    assert_eq!(job.state(), &BatchState::Succeeded);
+    assert!(job.state().is_done());
+    assert_eq!(job.batch_stats().request_count(), &Some(2));
+    assert_eq!(job.batch_stats().pending_request_count(), &None);
+
+    let results = &job
+        .output()
+        .as_ref()
+        .unwrap()
+        .inlined_responses
+        .as_ref()
+        .unwrap()
+        .inlined_responses;
+    assert_eq!(results[0].get_key(), Some("first"));
+    assert_eq!(
+        results[0]
+            .get_response()
+            .unwrap()
+            .get_chat()
+            .get_text_no_think(""),
+        "Hi"
+    );
+    assert_eq!(results[1].get_key(), Some("second"));
+    assert_eq!(results[1].clone().into_result().unwrap_err()["code"], 3);
+}
+
+#[test]
+fn batch_file_result_deserialization() {
+    let result: BatchResult =
+        serde_json::from_str(r#"{"key": "a", "error": {"code": 5, "message": "not found"}}"#)
+            .unwrap();
+    assert_eq!(result.get_key(), Some("a"));
+    assert!(result.get_response().is_none());
+}
+
+#[test]
+fn batch_request_serialization() {
+    let gemini = Gemini::new("API_KEY", "gemini-2.5-flash", Some("Be brief".into()));
+    let request = gemini.batch_request("greeting", Session::new(2).ask("Hi"));
+    assert_eq!(
+        request.to_inlined(),
+        json!({
+            "request": {
+                "systemInstruction": {"parts": [{"text": "Be brief"}]},
+                "contents": [{"role": "user", "parts": [{"text": "Hi"}]}]
+            },
+            "metadata": {"key": "greeting"}
+        })
+    );
+}
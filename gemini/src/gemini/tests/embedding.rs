@@ -0,0 +1,36 @@
+use crate::gemini::types::embedding::{ContentEmbedding, EmbedContentRequest, TaskType};
+use serde_json::json;
+
+#[test]
+fn embed_request_serialization() {
+    let request = EmbedContentRequest::from("Rust is fast")
+        .set_task_type(TaskType::RetrievalDocument)
+        .set_title("Rust")
+        .set_output_dimensionality(768);
+    assert_eq!(
+        json!(request),
+        json!({
+            "content": {"parts": [{"text": "Rust is fast"}]},
+            "taskType": "RETRIEVAL_DOCUMENT",
+            "title": "Rust",
+            "outputDimensionality": 768
+        })
+    );
+}
+
+#[test]
+fn cosine_similarity() {
+    let a = ContentEmbedding {
+        values: vec![1.0, 0.0],
+    };
+    let b = ContentEmbedding {
+        values: vec![0.0, 2.0],
+    };
+    assert_eq!(a.cosine_similarity(&a), 1.0);
+    assert_eq!(a.cosine_similarity(&b), 0.0);
+    let longer = ContentEmbedding {
+        values: vec![3.0, 0.0, 4.0],
+    };
+    assert_eq!(a.cosine_similarity(&longer), 1.0);
+    assert_eq!(longer.cosine_similarity(&a), 1.0);
+}
@@ -0,0 +1,33 @@
+use crate::gemini::ask::{ApiVersion, Endpoint};
+
+#[test]
+fn default_endpoint_url() {
+    assert_eq!(
+        Endpoint::default().url("models/gemini-2.5-flash:generateContent"),
+        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent"
+    );
+}
+
+#[test]
+fn custom_endpoint_url() {
+    let endpoint = Endpoint::new("http://localhost:8080/")
+        .set_api_version(ApiVersion::V1)
+        .set_path_prefix(Some("/proxy/gemini/"));
+    assert_eq!(
+        endpoint.url("cachedContents"),
+        "http://localhost:8080/proxy/gemini/v1/cachedContents"
+    );
+}
+
+#[test]
+fn upload_url() {
+    let endpoint = Endpoint::new("http://localhost:8080").set_path_prefix(Some("gemini"));
+    assert_eq!(
+        endpoint.upload_url("files"),
+        "http://localhost:8080/gemini/upload/v1beta/files"
+    );
+    assert_eq!(
+        endpoint.download_url("files/abc:download"),
+        "http://localhost:8080/gemini/download/v1beta/files/abc:download"
+    );
+}
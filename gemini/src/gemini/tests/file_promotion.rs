@@ -0,0 +1,73 @@
+use crate::gemini::ask::{Endpoint, FilePromotion, Gemini};
+use crate::gemini::types::files::File;
+use crate::gemini::types::request::{FileData, InlineData, PartType};
+use crate::gemini::types::sessions::Session;
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn small_and_foreign_parts_are_kept() {
+    let promotion = FilePromotion::new(1024);
+    let gemini = Gemini::new("API_KEY", "gemini-2.5-flash", None);
+    let mut session = Session::new(4);
+    session.ask_parts(vec![
+        InlineData::new(mime::IMAGE_PNG, "aGVsbG8=".into()).into(),
+        FileData::new(None, "https://example.com/file".into()).into(),
+    ]);
+    promotion.promote(&gemini, &mut session).await.unwrap();
+
+    let parts = session.get_last_chat().unwrap().parts();
+    assert!(matches!(parts[0].data(), PartType::InlineData(_)));
+    assert!(matches!(parts[1].data(), PartType::FileData(_)));
+    assert_eq!(promotion.get_cached_count(), 0);
+}
+
+#[test]
+fn expired_uploads_evicted() {
+    let promotion = FilePromotion::new(1).set_retention(Duration::ZERO);
+    let file = |uri: &str, expiration_time: &str| -> File {
+        serde_json::from_value(
+            json!({"name": "files/a", "uri": uri, "expirationTime": expiration_time}),
+        )
+        .unwrap()
+    };
+    promotion.remember(
+        InlineData::new(mime::IMAGE_PNG, "YQ==".into()),
+        &file("https://example.com/old", "2020-01-01T00:00:00Z"),
+    );
+    promotion.remember(
+        InlineData::new(mime::IMAGE_PNG, "Yg==".into()),
+        &file("https://example.com/new", "2999-01-01T00:00:00Z"),
+    );
+    assert_eq!(promotion.get_cached_count(), 2);
+    promotion.evict_expired();
+    assert_eq!(promotion.get_cached_count(), 1);
+}
+
+#[tokio::test]
+async fn cached_upload_used_only_for_same_bytes() {
+    let promotion = FilePromotion::new(1);
+    // Nothing listens there, so any upload fails.
+    let gemini = Gemini::new("API_KEY", "gemini-2.5-flash", None)
+        .set_endpoint(Endpoint::new("http://127.0.0.1:9"));
+    promotion.remember(
+        InlineData::new(mime::IMAGE_PNG, "YQ==".into()),
+        &serde_json::from_value(json!({
+            "name": "files/a",
+            "uri": "https://example.com/a",
+            "expirationTime": "2999-01-01T00:00:00Z"
+        }))
+        .unwrap(),
+    );
+    let mut session = Session::new(4);
+    session.ask_parts(vec![InlineData::new(mime::IMAGE_PNG, "YQ==".into()).into()]);
+    promotion.promote(&gemini, &mut session).await.unwrap();
+    let PartType::FileData(file_data) = session.get_last_chat().unwrap().parts()[0].data() else {
+        panic!("expected file data");
+    };
+    assert_eq!(file_data.file_uri(), "https://example.com/a");
+
+    let mut session = Session::new(4);
+    session.ask_parts(vec![InlineData::new(mime::IMAGE_PNG, "Yg==".into()).into()]);
+    assert!(promotion.promote(&gemini, &mut session).await.is_err());
+}
@@ -0,0 +1,87 @@
+use crate::gemini::ask::Gemini;
+use crate::gemini::types::files::{File, FileList, FileState};
+use crate::gemini::types::request::FileData;
+use serde_json::json;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn file_deserialization() {
+    let file: File = serde_json::from_value(json!({
+        "name": "files/abc-123",
+        "displayName": "report",
+        "mimeType": "application/pdf",
+        "sizeBytes": "2048",
+        "createTime": "2025-01-01T00:00:00Z",
+        "expirationTime": "2025-01-03T00:00:00Z",
+        "uri": "https://generativelanguage.googleapis.com/v1beta/files/abc-123",
+        "state": "ACTIVE",
+        "source": "UPLOADED"
+    }))
+    .unwrap();
+    assert_eq!(file.name(), "files/abc-123");
+    assert_eq!(file.get_size(), Some(2048));
+    assert!(file.is_active());
+
+    let file_data: FileData = file.into();
+    assert_eq!(file_data.mime_type().as_deref(), Some("application/pdf"));
+    assert_eq!(
+        file_data.file_uri(),
+        "https://generativelanguage.googleapis.com/v1beta/files/abc-123"
+    );
+}
+
+#[test]
+fn file_list_deserialization() {
+    let list: FileList = serde_json::from_value(json!({
+        "files": [{"name": "files/a", "state": "PROCESSING"}],
+        "nextPageToken": "next"
+    }))
+    .unwrap();
+    assert_eq!(list.files()[0].state(), &Some(FileState::Processing));
+    assert_eq!(list.next_page_token().as_deref(), Some("next"));
+
+    let empty: FileList = serde_json::from_value(json!({})).unwrap();
+    assert!(empty.files().is_empty());
+}
+
+#[test]
+fn page_token_url_encoded() {
+    let gemini = Gemini::new("KEY", "gemini-2.5-flash", None);
+    assert_eq!(
+        gemini.url_with_query(
+            "files",
+            [
+                ("pageSize", None),
+                ("pageToken", Some("a+b/c=".to_string()))
+            ]
+        ),
+        "https://generativelanguage.googleapis.com/v1beta/files?key=KEY&pageToken=a%2Bb%2Fc%3D"
+    );
+}
+
+#[test]
+fn file_expiration_time() {
+    let file = |expiration_time: &str| -> File {
+        serde_json::from_value(json!({"name": "files/a", "expirationTime": expiration_time}))
+            .unwrap()
+    };
+    let expected = SystemTime::UNIX_EPOCH + Duration::from_secs(1_412_262_083);
+    assert_eq!(
+        file("2014-10-02T15:01:23Z").get_expiration_time(),
+        Some(expected)
+    );
+    assert_eq!(
+        file("2014-10-02T20:31:23.5+05:30").get_expiration_time(),
+        Some(expected + Duration::from_millis(500))
+    );
+    assert_eq!(file("2 October 2014").get_expiration_time(), None);
+    assert_eq!(
+        file("2014-10-02T15:01:23.12345678é9Z").get_expiration_time(),
+        None
+    );
+    assert_eq!(file("2014-10-02T15:01:23.Z").get_expiration_time(), None);
+    assert_eq!(
+        file("99999999999999-10-02T15:01:23Z").get_expiration_time(),
+        None
+    );
+}
@@ -0,0 +1,31 @@
+use crate::gemini::types::models::{ModelInfo, ModelList};
+use serde_json::json;
+
+#[test]
+fn model_info_deserialization() {
+    let list: ModelList = serde_json::from_value(json!({
+        "models": [{
+            "name": "models/gemini-2.5-flash",
+            "version": "001",
+            "displayName": "Gemini 2.5 Flash",
+            "inputTokenLimit": 1048576,
+            "outputTokenLimit": 65536,
+            "supportedGenerationMethods": ["generateContent", "countTokens"],
+            "temperature": 1,
+            "maxTemperature": 2,
+            "topP": 0.95,
+            "topK": 64,
+            "thinking": true
+        }],
+        "nextPageToken": "next"
+    }))
+    .unwrap();
+    let model: &ModelInfo = &list.models()[0];
+    assert_eq!(model.get_id(), "gemini-2.5-flash");
+    assert_eq!(model.input_token_limit(), &Some(1048576));
+    assert_eq!(model.temperature(), &Some(1.0));
+    assert!(model.supports("countTokens"));
+    assert!(!model.supports("embedContent"));
+    assert!(model.supports_thinking());
+    assert_eq!(list.next_page_token().as_deref(), Some("next"));
+}
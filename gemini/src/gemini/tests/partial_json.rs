@@ -0,0 +1,87 @@
+use crate::gemini::utils::{PartialJson, parse_partial_json, partial_json_stream_typed};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+
+#[test]
+fn parse_every_prefix() {
+    let json = r#"{"name": "Café 😀", "tags": ["a", "b"], "count": 12, "ok": true, "none": null}"#;
+    for (end, _) in json.char_indices() {
+        // Every prefix parses to some snapshot, except the empty one.
+        let prefix = &json[..end];
+        assert_eq!(parse_partial_json(prefix).is_some(), end > 0, "{prefix}");
+    }
+    assert_eq!(
+        parse_partial_json(json),
+        Some(json!({"name": "Café 😀", "tags": ["a", "b"], "count": 12, "ok": true, "none": null}))
+    );
+}
+
+#[test]
+fn parse_incomplete() {
+    assert_eq!(
+        parse_partial_json(r#"{"a": "hel"#),
+        Some(json!({"a": "hel"}))
+    );
+    assert_eq!(parse_partial_json(r#"{"a": "x\"#), Some(json!({"a": "x"})));
+    assert_eq!(
+        parse_partial_json(r#"{"a": "\ud83d"#),
+        Some(json!({"a": ""}))
+    );
+    assert_eq!(parse_partial_json(r#"{"a": 1"#), Some(json!({})));
+    assert_eq!(parse_partial_json(r#"{"a": 1,"#), Some(json!({"a": 1})));
+    assert_eq!(parse_partial_json(r#"{"a": tr"#), Some(json!({})));
+    assert_eq!(parse_partial_json(r#"{"ke"#), Some(json!({})));
+    assert_eq!(
+        parse_partial_json(r#"[1, [2, {"b": ["#),
+        Some(json!([1, [2, {"b": []}]]))
+    );
+    assert_eq!(parse_partial_json("é"), None);
+    assert_eq!(parse_partial_json(r#"{"a": 1} x"#), None);
+    assert_eq!(parse_partial_json(r#"{"a" 1}"#), None);
+}
+
+#[test]
+fn partial_json_snapshots() {
+    let mut partial = PartialJson::default();
+    assert_eq!(partial.push(r#"{"items": ["#), Some(&json!({"items": []})));
+    assert_eq!(partial.push(" "), None);
+    assert_eq!(
+        partial.push(r#""one", "t"#),
+        Some(&json!({"items": ["one", "t"]}))
+    );
+    assert_eq!(partial.get_text(), r#"{"items": [ "one", "t"#);
+}
+
+#[tokio::test]
+async fn typed_partial_stream() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Partial {
+        title: Option<String>,
+        #[serde(default)]
+        items: Vec<String>,
+    }
+    let chunks = [r#"{"title": "Fru"#, r#"its", "items": ["app"#, r#"le"]}"#];
+    let snapshots: Vec<_> =
+        partial_json_stream_typed::<Partial, _, _, ()>(futures::stream::iter(chunks.map(Ok)))
+            .map(Result::unwrap)
+            .collect()
+            .await;
+    assert_eq!(
+        snapshots,
+        vec![
+            Partial {
+                title: Some("Fru".into()),
+                items: vec![]
+            },
+            Partial {
+                title: Some("Fruits".into()),
+                items: vec!["app".into()]
+            },
+            Partial {
+                title: Some("Fruits".into()),
+                items: vec!["apple".into()]
+            },
+        ]
+    );
+}
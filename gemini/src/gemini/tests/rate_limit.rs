@@ -0,0 +1,67 @@
+use crate::gemini::ask::RateLimiter;
+use crate::gemini::types::response::GeminiResponseStream;
+use crate::gemini::types::sessions::Session;
+use bytes::Bytes;
+use futures::StreamExt;
+use std::time::Duration;
+
+#[tokio::test]
+async fn max_in_flight() {
+    let limiter = RateLimiter::new(None, None, Some(1));
+    let shared = limiter.clone();
+    let permit = limiter.acquire().await;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), shared.acquire())
+            .await
+            .is_err()
+    );
+    drop(permit);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), shared.acquire())
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn requests_per_minute() {
+    let limiter = RateLimiter::new(Some(2), None, None);
+    limiter.acquire().await;
+    limiter.acquire().await;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn tokens_per_minute() {
+    let limiter = RateLimiter::new(None, Some(1000), None);
+    limiter.acquire().await.record_tokens(600);
+    limiter.acquire().await.record_tokens(600);
+    assert_eq!(limiter.get_tokens_spent(), 1200);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn dropped_stream_records_tokens() {
+    let limiter = RateLimiter::new(None, Some(1000), None);
+    let chunk = "data: {\"candidates\": [{\"content\": {\"role\": \"model\", \"parts\": [{\"text\": \"Hi\"}]}}], \"usageMetadata\": {\"promptTokenCount\": 40, \"totalTokenCount\": 42}, \"modelVersion\": \"gemini-2.5-flash\"}\r\n\r\n";
+    let bytes =
+        futures::stream::iter(vec![Ok(Bytes::from(chunk))]).chain(futures::stream::pending());
+    let mut stream = GeminiResponseStream::new(
+        Box::new(Box::pin(bytes)),
+        Session::new(4),
+        |_, response| response,
+        Some(limiter.acquire().await),
+    );
+    assert!(stream.next().await.unwrap().is_ok());
+    assert_eq!(limiter.get_tokens_spent(), 0);
+    drop(stream);
+    assert_eq!(limiter.get_tokens_spent(), 42);
+}
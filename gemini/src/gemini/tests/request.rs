@@ -0,0 +1,177 @@
+use crate::gemini::ask::Gemini;
+use crate::gemini::types::request::{
+    Chat, CountTokensRequestBody, GeminiRequestBody, GenerateContentRequest, GenerationConfig,
+    ImageConfig, InlineData, MediaResolution, Part, PartType, SpeechConfig, ThinkingConfig,
+    concatenate_parts, pcm_to_wav,
+};
+use crate::gemini::types::response::Modality;
+use crate::gemini::types::sessions::Session;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde_json::json;
+
+#[test]
+fn count_tokens_body() {
+    let mut session = Session::new(4);
+    session.ask("Hi");
+    let history = session.get_history();
+    let sys_prompt = "Be brief".into();
+    let body = CountTokensRequestBody::new(
+        None,
+        Some(GenerateContentRequest::new(
+            "models/gemini-2.5-flash".into(),
+            GeminiRequestBody::new(
+                Some(&sys_prompt),
+                None,
+                history.as_slice(),
+                None,
+                None,
+                None,
+                Some("cachedContents/123".into()),
+            ),
+        )),
+    );
+    assert_eq!(
+        json!(body),
+        json!({
+            "generateContentRequest": {
+                "model": "models/gemini-2.5-flash",
+                "systemInstruction": {"parts": [{"text": "Be brief"}]},
+                "contents": [{"role": "user", "parts": [{"text": "Hi"}]}],
+                "cachedContent": "cachedContents/123"
+            }
+        })
+    );
+}
+
+#[test]
+fn generation_config_merge() {
+    let mut ai = Gemini::new("API_KEY", "gemini-2.5-flash", None)
+        .set_thinking_config(ThinkingConfig::new_disable_thinking())
+        .set_json_mode(json!({"type": "STRING"}));
+    // Raw escape hatch in snake_case is replaced by the typed field.
+    ai.set_generation_config()["max_output_tokens"] = 10.into();
+    ai.set_generation_config()["customField"] = true.into();
+    let ai = ai.merge_generation_config(
+        GenerationConfig::default()
+            .set_temperature(0.5)
+            .set_max_output_tokens(100)
+            .set_stop_sequences(vec!["END".into()])
+            .set_media_resolution(MediaResolution::MediaResolutionLow),
+    );
+    assert_eq!(
+        ai.get_generation_config().unwrap(),
+        &json!({
+            "thinkingConfig": {"includeThoughts": false, "thinkingBudget": 0},
+            "responseMimeType": "application/json",
+            "responseSchema": {"type": "STRING"},
+            "customField": true,
+            "temperature": 0.5,
+            "maxOutputTokens": 100,
+            "stopSequences": ["END"],
+            "mediaResolution": "MEDIA_RESOLUTION_LOW"
+        })
+    );
+    let ai = ai.remove_json_mode();
+    assert!(
+        ai.get_generation_config()
+            .unwrap()
+            .get("responseSchema")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn generated_images() {
+    let chat: Chat = serde_json::from_value(json!({
+        "role": "model",
+        "parts": [
+            {"text": "Here is your cat"},
+            {"inlineData": {"mimeType": "image/png", "data": STANDARD.encode("draft")}, "thought": true},
+            {"inlineData": {"mimeType": "image/jpeg", "data": STANDARD.encode("cat")}},
+            {"inlineData": {"mimeType": "application/pdf", "data": STANDARD.encode("pdf")}}
+        ]
+    }))
+    .unwrap();
+    let images: Vec<_> = chat.get_images().collect();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].get_extension(), "jpg");
+    assert_eq!(images[0].decode().unwrap(), b"cat");
+
+    let prefix = std::env::temp_dir().join(format!("gemini_images_{}.v1", std::process::id()));
+    let paths = chat.save_images(prefix.to_string_lossy()).await.unwrap();
+    assert_eq!(paths.len(), 1);
+    assert!(paths[0].to_string_lossy().ends_with(".v1_0.jpg"));
+    assert_eq!(std::fs::read(&paths[0]).unwrap(), b"cat");
+    std::fs::remove_file(&paths[0]).unwrap();
+
+    let config = GenerationConfig::default()
+        .set_response_modalities(vec![Modality::Text, Modality::Image])
+        .set_image_config(ImageConfig::default().set_aspect_ratio("16:9"));
+    assert_eq!(
+        serde_json::to_value(config).unwrap(),
+        json!({"responseModalities": ["TEXT", "IMAGE"], "imageConfig": {"aspectRatio": "16:9"}})
+    );
+}
+
+#[test]
+fn streamed_thought_signature_kept() {
+    let mut parts: Vec<Part> = vec![Part::new(PartType::Text("Here is ".into()))];
+    let chunk: Part =
+        serde_json::from_value(json!({"text": "your cat", "thoughtSignature": "c2ln"})).unwrap();
+    concatenate_parts(&mut parts, &[chunk]);
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0].thought_signature().as_deref(), Some("c2ln"));
+}
+
+#[test]
+fn speech_config() {
+    let config = GenerationConfig::default().set_speech_config(
+        SpeechConfig::new_multi_speaker([("Joe", "Kore"), ("Jane", "Puck")])
+            .set_language_code("en-US"),
+    );
+    assert_eq!(
+        serde_json::to_value(config).unwrap(),
+        json!({"speechConfig": {
+            "multiSpeakerVoiceConfig": {"speakerVoiceConfigs": [
+                {"speaker": "Joe", "voiceConfig": {"prebuiltVoiceConfig": {"voiceName": "Kore"}}},
+                {"speaker": "Jane", "voiceConfig": {"prebuiltVoiceConfig": {"voiceName": "Puck"}}}
+            ]},
+            "languageCode": "en-US"
+        }})
+    );
+}
+
+#[test]
+fn pcm_audio_to_wav() {
+    let pcm = [1u8, 0, 2, 0, 3, 0];
+    let audio = InlineData::new(
+        "audio/L16;codec=pcm;rate=16000".parse().unwrap(),
+        STANDARD.encode(pcm),
+    );
+    assert_eq!(audio.get_sample_rate(), 16000);
+    let wav = audio.to_wav().unwrap();
+    assert_eq!(wav.len(), 44 + pcm.len());
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 36 + 6);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u16::from_le_bytes(wav[22..24].try_into().unwrap()), 1);
+    assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 16000);
+    assert_eq!(u32::from_le_bytes(wav[28..32].try_into().unwrap()), 32000);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(&wav[44..], pcm);
+
+    let wav_audio = InlineData::new("audio/wav".parse().unwrap(), STANDARD.encode("wav"));
+    assert_eq!(wav_audio.to_wav().unwrap(), b"wav");
+    let mp3 = InlineData::new("audio/mpeg".parse().unwrap(), STANDARD.encode("mp3"));
+    assert_eq!(
+        mp3.to_wav().unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+    for channels in [0, u16::MAX] {
+        assert_eq!(
+            pcm_to_wav(&pcm, 16000, channels).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+    assert!(pcm_to_wav(&pcm, u32::MAX, 2).is_err());
+}
@@ -0,0 +1,239 @@
+use crate::gemini::types::request::HarmCategory;
+use crate::gemini::types::response::{
+    BlockReason, CandidateSelector, GeminiResponse, HarmProbability, Modality, UrlRetrievalStatus,
+    UsageMetadata,
+};
+use crate::gemini::types::sessions::Session;
+use serde_json::json;
+
+#[test]
+fn usage_metadata_deserialization() {
+    let response: GeminiResponse = serde_json::from_value(json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": "Hi"}]}}],
+        "usageMetadata": {
+            "promptTokenCount": 10,
+            "candidatesTokenCount": 2,
+            "thoughtsTokenCount": 30,
+            "totalTokenCount": 42,
+            "promptTokensDetails": [{"modality": "TEXT", "tokenCount": 10}]
+        },
+        "modelVersion": "gemini-2.5-flash"
+    }))
+    .unwrap();
+    let usage = &response.usage_metadata;
+    assert_eq!(usage.total_token_count, Some(42));
+    assert_eq!(usage.thoughts_token_count, Some(30));
+    assert_eq!(usage.cached_content_token_count, None);
+    assert_eq!(usage.prompt_tokens_details[0].modality, Modality::Text);
+}
+
+#[test]
+fn usage_metadata_update() {
+    let mut usage: UsageMetadata = serde_json::from_value(json!({
+        "promptTokenCount": 10,
+        "totalTokenCount": 10
+    }))
+    .unwrap();
+    usage.update(
+        &serde_json::from_value(json!({
+            "candidatesTokenCount": 5,
+            "totalTokenCount": 15
+        }))
+        .unwrap(),
+    );
+    assert_eq!(usage.prompt_token_count, Some(10));
+    assert_eq!(usage.candidates_token_count, Some(5));
+    assert_eq!(usage.total_token_count, Some(15));
+}
+
+fn multi_candidate_response() -> GeminiResponse {
+    serde_json::from_value(json!({
+        "candidates": [
+            {"content": {"role": "model", "parts": [{"text": "Hi"}]}, "finishReason": "SAFETY"},
+            {"content": {"role": "model", "parts": [{"text": "Hello there"}]}, "index": 1},
+            {"content": {"role": "model", "parts": [{"text": "Hey"}]}, "index": 2}
+        ],
+        "modelVersion": "gemini-2.5-flash"
+    }))
+    .unwrap()
+}
+
+#[test]
+fn candidate_accessors() {
+    let response = multi_candidate_response();
+    assert_eq!(response.get_candidates().len(), 3);
+    assert_eq!(
+        response.get_candidate(2).unwrap().content.get_text_all(""),
+        "Hey"
+    );
+    assert!(response.get_candidate(3).is_none());
+    assert!(response.get_candidates()[0].is_blocked());
+    assert_eq!(response.get_chats()[1].get_text_all(""), "Hello there");
+}
+
+#[test]
+fn candidate_selection() {
+    let response = multi_candidate_response();
+    let candidates = response.get_candidates();
+    assert_eq!(CandidateSelector::First.select(candidates), Some(0));
+    assert_eq!(CandidateSelector::Longest.select(candidates), Some(1));
+    assert_eq!(
+        CandidateSelector::FirstNotBlocked.select(candidates),
+        Some(1)
+    );
+    let last = CandidateSelector::Custom(std::sync::Arc::new(|candidates| candidates.len() - 1));
+    assert_eq!(last.select(candidates), Some(2));
+    assert_eq!(CandidateSelector::Longest.select(&[]), None);
+
+    let mut session = Session::new(4).set_candidate_selector(CandidateSelector::Longest);
+    session.ask("Greet me");
+    session.update(&response);
+    assert_eq!(
+        session.get_last_chat().unwrap().get_text_all(""),
+        "Hello there"
+    );
+}
+
+#[test]
+fn streamed_candidate_selection() {
+    let mut session = Session::new(4).set_candidate_selector(CandidateSelector::Longest);
+    session.ask("Greet me");
+    let response = multi_candidate_response();
+    session.update_stream(&response);
+    assert_eq!(session.get_last_chat().unwrap().get_text_all(""), "Hi");
+    session.select_streamed_candidate(response.get_candidates());
+    assert_eq!(session.get_history_length(), 2);
+    assert_eq!(
+        session.get_last_chat().unwrap().get_text_all(""),
+        "Hello there"
+    );
+}
+
+#[test]
+fn grounding_metadata() {
+    let response: GeminiResponse = serde_json::from_value(json!({
+        "candidates": [{
+            "content": {"role": "model", "parts": [{"text": "Spain won Euro 2024. Héllo."}]},
+            "finishReason": "STOP",
+            "groundingMetadata": {
+                "webSearchQueries": ["euro 2024 winner"],
+                "searchEntryPoint": {"renderedContent": "<div></div>"},
+                "groundingChunks": [
+                    {"web": {"uri": "https://a.example", "title": "a.example"}},
+                    {"web": {"uri": "https://b.example", "title": "b.example"}}
+                ],
+                "groundingSupports": [
+                    {"segment": {"endIndex": 20, "text": "Spain won Euro 2024."}, "groundingChunkIndices": [0, 1]},
+                    {"segment": {"startIndex": 21, "endIndex": 28}, "groundingChunkIndices": [1]}
+                ]
+            },
+            "citationMetadata": {"citationSources": [{"startIndex": 0, "endIndex": 20, "uri": "https://a.example"}]},
+            "urlContextMetadata": {"urlMetadata": [
+                {"retrievedUrl": "https://a.example", "urlRetrievalStatus": "URL_RETRIEVAL_STATUS_SUCCESS"}
+            ]}
+        }],
+        "modelVersion": "gemini-2.5-flash"
+    }))
+    .unwrap();
+    let candidate = &response.get_candidates()[0];
+    let grounding = candidate.grounding_metadata.as_ref().unwrap();
+    assert_eq!(grounding.web_search_queries, vec!["euro 2024 winner"]);
+    assert_eq!(
+        grounding.grounding_chunks[1].get_uri(),
+        Some("https://b.example")
+    );
+    assert_eq!(grounding.grounding_supports[1].segment.start_index, 21);
+    assert_eq!(
+        candidate
+            .citation_metadata
+            .as_ref()
+            .unwrap()
+            .citation_sources[0]
+            .end_index,
+        Some(20)
+    );
+    assert_eq!(
+        candidate
+            .url_context_metadata
+            .as_ref()
+            .unwrap()
+            .url_metadata[0]
+            .url_retrieval_status,
+        UrlRetrievalStatus::UrlRetrievalStatusSuccess
+    );
+    assert_eq!(
+        candidate.get_text_with_citations(""),
+        "Spain won Euro 2024.[1][2] Héllo.[2]"
+    );
+}
+
+#[test]
+fn blocked_prompt() {
+    let response: GeminiResponse = serde_json::from_value(json!({
+        "promptFeedback": {
+            "blockReason": "SAFETY",
+            "safetyRatings": [
+                {"category": "HARM_CATEGORY_CIVIC_INTEGRITY", "probability": "HIGH", "blocked": true},
+                {"category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE"}
+            ]
+        },
+        "modelVersion": "gemini-2.5-flash"
+    }))
+    .unwrap();
+    assert!(response.is_blocked());
+    assert_eq!(response.get_block_reason(), Some(&BlockReason::Safety));
+    let ratings = &response.get_prompt_feedback().unwrap().safety_ratings;
+    assert_eq!(
+        ratings[0].category,
+        HarmCategory::HarmCategoryCivicIntegrity
+    );
+    assert!(ratings[0].blocked);
+    assert!(ratings[1].probability < HarmProbability::Low);
+    assert!(response.get_chat().parts().is_empty());
+    assert_eq!(response.get_finish_reason(), None);
+
+    let mut session = Session::new(6);
+    session.ask("Hi");
+    assert!(session.update(&response).is_none());
+}
+
+#[test]
+fn blocked_candidate() {
+    let response: GeminiResponse = serde_json::from_value(json!({
+        "candidates": [{
+            "finishReason": "SAFETY",
+            "safetyRatings": [{"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "MEDIUM"}]
+        }],
+        "modelVersion": "gemini-2.5-flash"
+    }))
+    .unwrap();
+    assert!(response.is_blocked());
+    assert_eq!(response.get_block_reason(), None);
+    assert_eq!(
+        response.get_candidates()[0].safety_ratings[0].probability,
+        HarmProbability::Medium
+    );
+    assert!(!multi_candidate_response().is_blocked());
+
+    let empty: GeminiResponse =
+        serde_json::from_value(json!({"modelVersion": "gemini-2.5-flash"})).unwrap();
+    assert!(!empty.is_blocked());
+}
+
+#[test]
+fn unknown_safety_values() {
+    let response: GeminiResponse = serde_json::from_value(json!({
+        "promptFeedback": {
+            "blockReason": "SOME_NEW_REASON",
+            "safetyRatings": [{"category": "HARM_CATEGORY_NEW", "probability": "VERY_HIGH"}]
+        },
+        "modelVersion": "gemini-2.5-flash"
+    }))
+    .unwrap();
+    assert!(response.is_blocked());
+    assert_eq!(response.get_block_reason(), Some(&BlockReason::Unknown));
+    let rating = &response.get_prompt_feedback().unwrap().safety_ratings[0];
+    assert_eq!(rating.category, HarmCategory::Unknown);
+    assert_eq!(rating.probability, HarmProbability::Unknown);
+    assert!(rating.probability > HarmProbability::High);
+}
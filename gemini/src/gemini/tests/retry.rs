@@ -0,0 +1,104 @@
+use crate::gemini::ask::RetryPolicy;
+use crate::gemini::error::{Error, GeminiError, Status};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::time::Duration;
+
+fn error(status: Status, details: Option<Vec<serde_json::Value>>) -> Error {
+    Error {
+        code: StatusCode::TOO_MANY_REQUESTS,
+        message: "".into(),
+        status,
+        details,
+    }
+}
+
+#[test]
+fn exponential_backoff() {
+    let policy = RetryPolicy::default()
+        .set_jitter(false)
+        .set_max_attempts(5)
+        .set_max_delay(Duration::from_secs(3));
+    let error = error(Status::Unavailable, None);
+    assert_eq!(policy.delay(1, &error), Some(Duration::from_secs(1)));
+    assert_eq!(policy.delay(2, &error), Some(Duration::from_secs(2)));
+    assert_eq!(policy.delay(3, &error), Some(Duration::from_secs(3)));
+    assert_eq!(policy.delay(5, &error), None);
+}
+
+#[test]
+fn jitter_within_half() {
+    let policy = RetryPolicy::default();
+    let delay = policy.delay(2, &error(Status::Internal, None)).unwrap();
+    assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+}
+
+#[test]
+fn non_retryable_status() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.delay(1, &error(Status::InvalidArgument, None)), None);
+}
+
+#[test]
+fn honours_retry_info() {
+    let error = error(
+        Status::ResourceExhausted,
+        Some(vec![
+            json!({"@type": "type.googleapis.com/google.rpc.QuotaFailure"}),
+            json!({
+                "@type": "type.googleapis.com/google.rpc.RetryInfo",
+                "retryDelay": "37.5s"
+            }),
+        ]),
+    );
+    assert_eq!(error.retry_delay(), Some(Duration::from_millis(37500)));
+    assert_eq!(
+        RetryPolicy::default().delay(1, &error),
+        Some(Duration::from_millis(37500))
+    );
+}
+
+#[test]
+fn retry_info_capped_at_max_delay() {
+    let error = error(
+        Status::ResourceExhausted,
+        Some(vec![json!({
+            "@type": "type.googleapis.com/google.rpc.RetryInfo",
+            "retryDelay": "3600s"
+        })]),
+    );
+    let policy = RetryPolicy::default().set_max_delay(Duration::from_secs(10));
+    assert_eq!(policy.delay(1, &error), Some(Duration::from_secs(10)));
+}
+
+#[test]
+fn non_json_error_body() {
+    let error = GeminiError::from_body(StatusCode::SERVICE_UNAVAILABLE, "<html>Bad gateway</html>");
+    assert_eq!(error.error.status, Status::Unavailable);
+    assert_eq!(error.error.message, "<html>Bad gateway</html>");
+    assert!(RetryPolicy::default().delay(1, &error.error).is_some());
+
+    let error = GeminiError::from_body(
+        StatusCode::BAD_REQUEST,
+        r#"{"error": {"code": 400, "message": "Bad", "status": "SOMETHING_NEW"}}"#,
+    );
+    assert_eq!(error.error.status, Status::Unknown);
+    assert_eq!(error.error.message, "Bad");
+    assert_eq!(RetryPolicy::default().delay(1, &error.error), None);
+}
+
+#[test]
+fn non_idempotent_retried_on_resource_exhausted_only() {
+    let policy = RetryPolicy::default().set_jitter(false);
+    let unavailable = error(Status::Unavailable, None);
+    assert_eq!(policy.delay_non_idempotent(1, &unavailable), None);
+    assert_eq!(
+        policy.delay_non_idempotent(1, &error(Status::ResourceExhausted, None)),
+        Some(Duration::from_secs(1))
+    );
+    let policy = policy.set_retry_non_idempotent(true);
+    assert_eq!(
+        policy.delay_non_idempotent(1, &unavailable),
+        Some(Duration::from_secs(1))
+    );
+}
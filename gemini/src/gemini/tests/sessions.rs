@@ -0,0 +1,96 @@
+use crate::gemini::types::request::{Chat, FunctionCall, Role};
+use crate::gemini::types::sessions::{Session, TokenCounter};
+use serde_json::json;
+
+/// A token per character of text.
+#[derive(Debug)]
+struct CharCounter;
+impl TokenCounter for CharCounter {
+    fn count_tokens(&self, chat: &Chat) -> u32 {
+        chat.get_text_all("").len() as u32
+    }
+}
+
+#[test]
+fn token_limit_pops_oldest() {
+    let mut session = Session::new(100)
+        .set_token_limit(Some(10))
+        .set_token_counter(CharCounter);
+    session.ask("aaaa").reply("bbbb").ask("cccc");
+    assert_eq!(session.get_history_length(), 2);
+    assert_eq!(session.get_history()[0].get_text_all(""), "bbbb");
+    assert_eq!(session.count_history_tokens(), 8);
+}
+
+#[test]
+fn token_limit_keeps_latest_chat() {
+    let mut session = Session::new(100)
+        .set_token_limit(Some(5))
+        .set_token_counter(CharCounter);
+    session.ask("aaaa").reply("a very long reply");
+    assert_eq!(session.get_history_length(), 1);
+    assert_eq!(*session.get_history()[0].role(), Role::Model);
+}
+
+#[test]
+fn token_limit_never_starts_with_function() {
+    let mut session = Session::new(100)
+        .set_token_limit(Some(12))
+        .set_token_counter(CharCounter);
+    session.ask("aaaa");
+    session.reply_parts(vec![FunctionCall::new("f".into(), None).into()]);
+    session.add_function_response("f", json!({})).unwrap();
+    session.reply("bbbb").ask("cccccccc");
+    let history = session.get_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(*history[0].role(), Role::Model);
+    assert_eq!(history[0].get_text_all(""), "bbbb");
+}
+
+#[test]
+fn token_limit_keeps_call_of_latest_function_response() {
+    let mut session = Session::new(100)
+        .set_token_limit(Some(5))
+        .set_token_counter(CharCounter);
+    session.ask("aaaa").reply("bbbb").ask("cccc");
+    session.reply_parts(vec![
+        "Calling f".into(),
+        FunctionCall::new("f".into(), None).into(),
+    ]);
+    session.add_function_response("f", json!({})).unwrap();
+    let history = session.get_history();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].get_text_all(""), "cccc");
+    assert!(history[1].has_function_call());
+    assert_eq!(*history[2].role(), Role::Function);
+}
+
+#[test]
+fn compact_replaces_oldest_chats() {
+    let mut session = Session::new(100);
+    session.ask("My name is Ram").reply("Hi Ram");
+    session.reply_parts(vec![FunctionCall::new("f".into(), None).into()]);
+    session.add_function_response("f", json!({})).unwrap();
+    session.reply("Done").ask("What is my name?");
+
+    // Can't leave history starting with the function response.
+    let chat_count = session.get_compactable_chat_count(2).unwrap();
+    assert_eq!(chat_count, 4);
+    let replaced = session.compact(chat_count, "User's name is Ram", true);
+    assert_eq!(replaced.len(), 4);
+    assert_eq!(session.get_archive().len(), 4);
+
+    let history = session.get_history();
+    assert_eq!(history.len(), 3);
+    assert_eq!(*history[0].role(), Role::User);
+    assert!(history[0].get_text_all("").contains("User's name is Ram"));
+    assert_eq!(*history[1].role(), Role::Model);
+    assert_eq!(history[2].get_text_all(""), "What is my name?");
+}
+
+#[test]
+fn compact_needs_user_prompt_after() {
+    let mut session = Session::new(100);
+    session.ask("Hi").reply("Hello");
+    assert_eq!(session.get_compactable_chat_count(1), None);
+}
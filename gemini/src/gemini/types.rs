@@ -1,4 +1,8 @@
+pub mod batch;
 pub mod caching;
+pub mod embedding;
+pub mod files;
+pub mod models;
 pub mod request;
 pub mod response;
 pub mod sessions;
@@ -0,0 +1,187 @@
+use super::response::GeminiResponse;
+use getset::Getters;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+
+/// int64 fields are sent as JSON strings.
+fn deserialize_count<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(count)) => count.parse().map(Some).map_err(serde::de::Error::custom),
+        Some(Value::Number(count)) => Ok(count.as_u64()),
+        _ => Ok(None),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchState {
+    #[serde(rename = "BATCH_STATE_UNSPECIFIED", alias = "JOB_STATE_UNSPECIFIED")]
+    Unspecified,
+    /// The batch is waiting to be run.
+    #[serde(rename = "BATCH_STATE_PENDING", alias = "JOB_STATE_PENDING")]
+    Pending,
+    #[serde(rename = "BATCH_STATE_RUNNING", alias = "JOB_STATE_RUNNING")]
+    Running,
+    #[serde(rename = "BATCH_STATE_SUCCEEDED", alias = "JOB_STATE_SUCCEEDED")]
+    Succeeded,
+    #[serde(rename = "BATCH_STATE_FAILED", alias = "JOB_STATE_FAILED")]
+    Failed,
+    #[serde(rename = "BATCH_STATE_CANCELLED", alias = "JOB_STATE_CANCELLED")]
+    Cancelled,
+    /// The batch didn't finish within 48 hours.
+    #[serde(rename = "BATCH_STATE_EXPIRED", alias = "JOB_STATE_EXPIRED")]
+    Expired,
+}
+impl BatchState {
+    /// If true, the state won't change anymore.
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self,
+            BatchState::Succeeded
+                | BatchState::Failed
+                | BatchState::Cancelled
+                | BatchState::Expired
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchStats {
+    #[serde(default, deserialize_with = "deserialize_count")]
+    #[get = "pub"]
+    request_count: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_count")]
+    #[get = "pub"]
+    successful_request_count: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_count")]
+    #[get = "pub"]
+    failed_request_count: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_count")]
+    #[get = "pub"]
+    pending_request_count: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InlinedResponses {
+    #[serde(default)]
+    pub inlined_responses: Vec<BatchResult>,
+}
+
+/// Where results of a finished batch are.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOutput {
+    /// Files API name of the JSONL results, for batches submitted as a file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    responses_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) inlined_responses: Option<InlinedResponses>,
+}
+
+/// A [batch](https://ai.google.dev/gemini-api/docs/batch-mode) job.
+#[derive(Serialize, Deserialize, Clone, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchJob {
+    /// Format: `batches/{id}`
+    #[serde(default)]
+    #[get = "pub"]
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    model: Option<String>,
+    #[get = "pub"]
+    state: BatchState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    create_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    update_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    end_time: Option<String>,
+    #[serde(default)]
+    #[get = "pub"]
+    batch_stats: BatchStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    output: Option<BatchOutput>,
+}
+
+/// Long running operation returned by batch methods, holding the job in `metadata`.
+#[derive(Deserialize)]
+pub(crate) struct BatchOperation {
+    name: String,
+    metadata: BatchJob,
+}
+impl From<BatchOperation> for BatchJob {
+    fn from(operation: BatchOperation) -> Self {
+        let mut job = operation.metadata;
+        if job.name.is_empty() {
+            job.name = operation.name;
+        }
+        job
+    }
+}
+
+/// One request of a batch, identified by `key` in its result.
+/// Build by `Gemini::batch_request`.
+#[derive(Serialize, Deserialize, Clone, Debug, Getters)]
+pub struct BatchRequest {
+    #[get = "pub"]
+    key: String,
+    /// Serialized `GeminiRequestBody`.
+    #[get = "pub"]
+    request: Value,
+}
+impl BatchRequest {
+    pub(crate) fn new(key: String, request: Value) -> Self {
+        Self { key, request }
+    }
+    /// Format of requests sent inline, where the key goes in the metadata.
+    pub(crate) fn to_inlined(&self) -> Value {
+        json!({"request": self.request, "metadata": {"key": self.key}})
+    }
+}
+
+/// Result of a `BatchRequest`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<GeminiResponse>,
+    /// `google.rpc.Status` of the failed request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Value>,
+}
+impl BatchResult {
+    /// `BatchRequest::key` of the request this result is of.
+    pub fn get_key(&self) -> Option<&str> {
+        self.key
+            .as_deref()
+            .or_else(|| self.metadata.as_ref()?["key"].as_str())
+    }
+    pub fn get_response(&self) -> Option<&GeminiResponse> {
+        self.response.as_ref()
+    }
+    pub fn get_error(&self) -> Option<&Value> {
+        self.error.as_ref()
+    }
+    pub fn into_result(self) -> Result<GeminiResponse, Value> {
+        match self.response {
+            Some(response) => Ok(response),
+            None => Err(self.error.unwrap_or_default()),
+        }
+    }
+}
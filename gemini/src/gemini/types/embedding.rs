@@ -0,0 +1,145 @@
+use super::request::Part;
+use derive_new::new;
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+/// Intended use of an embedding, to optimize it for that use.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TaskType {
+    /// Unset value, which will default to one of the other enum values.
+    TaskTypeUnspecified,
+    /// The text is a query in a search/retrieval setting.
+    RetrievalQuery,
+    /// The text is a document from the corpus being searched.
+    RetrievalDocument,
+    /// The text is used for Semantic Text Similarity.
+    SemanticSimilarity,
+    /// The text is to be classified.
+    Classification,
+    /// The embeddings are used for clustering.
+    Clustering,
+    /// The text is a question to be answered.
+    QuestionAnswering,
+    /// The text is a statement to be verified.
+    FactVerification,
+    /// The text is a natural language query to retrieve code.
+    CodeRetrievalQuery,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Getters, new)]
+pub struct EmbedContent {
+    #[get = "pub"]
+    parts: Vec<Part>,
+}
+
+/// Content to embed with its options.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::types::embedding::{EmbedContentRequest, TaskType};
+/// let request = EmbedContentRequest::from("What is the meaning of life?")
+///     .set_task_type(TaskType::RetrievalQuery)
+///     .set_output_dimensionality(768);
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedContentRequest {
+    /// Format: `models/{model}`. Filled by `Gemini::embed_batch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    model: Option<String>,
+    #[get = "pub"]
+    content: EmbedContent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    task_type: Option<TaskType>,
+    /// Title of the document. Only for `TaskType::RetrievalDocument`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    title: Option<String>,
+    /// Truncates the embedding to this size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    output_dimensionality: Option<u32>,
+}
+impl EmbedContentRequest {
+    /// `parts` can be text or `InlineData` for multimodal embedding.
+    pub fn new(parts: Vec<Part>) -> Self {
+        Self {
+            model: None,
+            content: EmbedContent::new(parts),
+            task_type: None,
+            title: None,
+            output_dimensionality: None,
+        }
+    }
+    pub fn set_task_type(mut self, task_type: TaskType) -> Self {
+        self.task_type = Some(task_type);
+        self
+    }
+    pub fn set_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+    pub fn set_output_dimensionality(mut self, output_dimensionality: u32) -> Self {
+        self.output_dimensionality = Some(output_dimensionality);
+        self
+    }
+    pub(crate) fn set_model(&mut self, model: impl Into<String>) {
+        self.model = Some(model.into());
+    }
+}
+impl From<Vec<Part>> for EmbedContentRequest {
+    fn from(parts: Vec<Part>) -> Self {
+        Self::new(parts)
+    }
+}
+impl From<Part> for EmbedContentRequest {
+    fn from(part: Part) -> Self {
+        Self::new(vec![part])
+    }
+}
+impl From<String> for EmbedContentRequest {
+    fn from(text: String) -> Self {
+        Self::new(vec![text.into()])
+    }
+}
+impl From<&str> for EmbedContentRequest {
+    fn from(text: &str) -> Self {
+        Self::new(vec![text.into()])
+    }
+}
+
+#[derive(Serialize, new)]
+pub struct BatchEmbedContentsRequestBody<'a> {
+    requests: &'a [EmbedContentRequest],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ContentEmbedding {
+    pub values: Vec<f32>,
+}
+impl ContentEmbedding {
+    /// Cosine similarity with `other`. Embeddings of different sizes are compared upto the
+    /// length of the shorter one, ignoring the rest of the longer one.
+    pub fn cosine_similarity(&self, other: &ContentEmbedding) -> f32 {
+        let len = self.values.len().min(other.values.len());
+        let (a, b) = (&self.values[..len], &other.values[..len]);
+        let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+        let norm = |values: &[f32]| values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let norms = norm(a) * norm(b);
+        if norms == 0.0 { 0.0 } else { dot / norms }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EmbedContentResponse {
+    pub embedding: ContentEmbedding,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchEmbedContentsResponse {
+    #[serde(default)]
+    pub embeddings: Vec<ContentEmbedding>,
+}
@@ -0,0 +1,173 @@
+use super::request::FileData;
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, SystemTime};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FileState {
+    /// The default value. This value is used if the state is omitted.
+    StateUnspecified,
+    /// File is being processed and cannot be used for inference yet.
+    Processing,
+    /// File is processed and available for inference.
+    Active,
+    /// File failed processing.
+    Failed,
+}
+
+/// A file uploaded to the [Files API](https://ai.google.dev/api/files).
+/// Files are deleted automatically 48 hours after upload.
+#[derive(Serialize, Deserialize, Clone, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct File {
+    /// The resource name of the file. Format: `files/{id}`
+    #[serde(default)]
+    #[get = "pub"]
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    mime_type: Option<String>,
+    /// Size in bytes, as a decimal string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    size_bytes: Option<String>,
+    /// RFC 3339 format e.g. '2014-10-02T15:01:23Z'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    create_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    update_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    expiration_time: Option<String>,
+    /// Base64 encoded SHA-256 hash of the uploaded bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    sha256_hash: Option<String>,
+    /// URI to use in `FileData`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    download_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    state: Option<FileState>,
+    /// Error status if processing failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    error: Option<Value>,
+}
+impl File {
+    pub fn get_size(&self) -> Option<u64> {
+        self.size_bytes.as_ref()?.parse().ok()
+    }
+    pub fn is_active(&self) -> bool {
+        self.state == Some(FileState::Active)
+    }
+    /// `expiration_time` parsed. `None` if missing or not in RFC 3339 format.
+    pub fn get_expiration_time(&self) -> Option<SystemTime> {
+        parse_rfc3339(self.expiration_time.as_ref()?)
+    }
+    /// `FileData` referring to this file, ready for `Session::ask`.
+    pub fn to_file_data(&self) -> FileData {
+        FileData::new(self.mime_type.clone(), self.uri.clone().unwrap_or_default())
+    }
+}
+impl From<File> for FileData {
+    fn from(file: File) -> Self {
+        file.to_file_data()
+    }
+}
+
+/// Parses time like "2014-10-02T15:01:23.045123456Z" or "2014-10-02T20:31:23+05:30", since
+/// 1970.
+fn parse_rfc3339(time: &str) -> Option<SystemTime> {
+    fn is_digits(text: &str) -> bool {
+        !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit())
+    }
+    // Upto 9 digits, so that the arithmetic below can't overflow.
+    fn number(text: &str) -> Option<i64> {
+        match is_digits(text) && text.len() <= 9 {
+            true => text.parse().ok(),
+            false => None,
+        }
+    }
+    let (date, time) = time.split_once(['T', 't', ' '])?;
+    let mut date = date.splitn(3, '-');
+    let (year, month, day) = (
+        number(date.next()?)?,
+        number(date.next()?)?,
+        number(date.next()?)?,
+    );
+    let (time, offset) = match time.strip_suffix(['Z', 'z']) {
+        Some(time) => (time, 0),
+        None => {
+            let sign_at = time.rfind(['+', '-'])?;
+            let (time, offset) = time.split_at(sign_at);
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let seconds = number(hours)? * 3600 + number(minutes)? * 60;
+            (
+                time,
+                if offset.starts_with('-') {
+                    -seconds
+                } else {
+                    seconds
+                },
+            )
+        }
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+    let mut time = time.splitn(3, ':');
+    let (hour, minute, second) = (
+        number(time.next()?)?,
+        number(time.next()?)?,
+        number(time.next()?)?,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Days since 1970-01-01 of the proleptic Gregorian date.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    let nanos = match fraction {
+        None => 0,
+        Some(fraction) if is_digits(fraction) => {
+            // Only ASCII digits, so slicing by bytes can't split a character.
+            let digits = &fraction[..fraction.len().min(9)];
+            number(digits)? as u32 * 10u32.pow(9 - digits.len() as u32)
+        }
+        _ => return None,
+    };
+    SystemTime::UNIX_EPOCH.checked_add(Duration::new(seconds.try_into().ok()?, nanos))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct FileList {
+    #[serde(default)]
+    #[get = "pub"]
+    files: Vec<File>,
+    #[get = "pub"]
+    next_page_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct FileResponse {
+    pub file: File,
+}
@@ -0,0 +1,81 @@
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+/// Information about a generative model. See [models](https://ai.google.dev/api/models#Model).
+#[derive(Serialize, Deserialize, Debug, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    /// The resource name of the model. Format: `models/{model}`
+    #[get = "pub"]
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    base_model_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    description: Option<String>,
+    /// Maximum number of input tokens allowed for this model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    input_token_limit: Option<u32>,
+    /// Maximum number of output tokens available for this model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    output_token_limit: Option<u32>,
+    /// Supported API methods like "generateContent", "countTokens" or "embedContent".
+    #[serde(default)]
+    #[get = "pub"]
+    supported_generation_methods: Vec<String>,
+    /// Whether the model supports thinking.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    thinking: Option<bool>,
+    /// Default temperature of the model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    max_temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    top_k: Option<u32>,
+}
+impl ModelInfo {
+    /// Model name without "models/" prefix, as accepted by `Gemini::new`.
+    pub fn get_id(&self) -> &str {
+        self.name.strip_prefix("models/").unwrap_or(&self.name)
+    }
+    pub fn supports(&self, method: &str) -> bool {
+        self.supported_generation_methods
+            .iter()
+            .any(|supported| supported == method)
+    }
+    pub fn supports_thinking(&self) -> bool {
+        self.thinking == Some(true)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelList {
+    #[serde(default)]
+    #[get = "pub"]
+    models: Vec<ModelInfo>,
+    #[get = "pub"]
+    next_page_token: Option<String>,
+}
+impl ModelList {
+    pub(crate) fn into_parts(self) -> (Vec<ModelInfo>, Option<String>) {
+        (self.models, self.next_page_token)
+    }
+}
@@ -9,7 +9,12 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use std::str::FromStr;
 mod chat;
+mod generation_config;
 pub use chat::Chat;
+pub use generation_config::{
+    GenerationConfig, ImageConfig, MediaResolution, MultiSpeakerVoiceConfig, PrebuiltVoiceConfig,
+    SpeakerVoiceConfig, SpeechConfig, VoiceConfig,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -37,7 +42,8 @@ pub struct InlineData {
     #[get = "pub"]
     #[serde(
         deserialize_with = "deserialize_mime",
-        serialize_with = "serialize_mime"
+        serialize_with = "serialize_mime",
+        alias = "mimeType"
     )]
     mime_type: Mime,
     #[get = "pub"]
@@ -110,6 +116,133 @@ impl InlineData {
         let data = tokio::fs::read(file_path).await?;
         Ok(InlineData::new(mime_type, STANDARD.encode(data)))
     }
+
+    /// Decodes `data` to bytes.
+    pub fn decode(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        STANDARD.decode(&self.data)
+    }
+    /// File extension for `mime_type`, like "png" for "image/png". Falls back to the subtype.
+    pub fn get_extension(&self) -> &str {
+        match (
+            self.mime_type.type_().as_str(),
+            self.mime_type.subtype().as_str(),
+        ) {
+            ("image", "jpeg") => "jpg",
+            ("image", "svg") => "svg",
+            ("audio", "mpeg") => "mp3",
+            ("audio", "x-wav" | "wave") => "wav",
+            ("text", "plain") => "txt",
+            (_, subtype) => subtype,
+        }
+    }
+    /// Sample rate of raw PCM audio like "audio/L16;codec=pcm;rate=24000". 24000 if not given,
+    /// as in replies of TTS models.
+    pub fn get_sample_rate(&self) -> u32 {
+        self.mime_type
+            .get_param("rate")
+            .and_then(|rate| rate.as_str().parse().ok())
+            .unwrap_or(24000)
+    }
+    /// Decodes the audio and wraps it in a WAV container if it is raw 16 bit PCM ("audio/L16"),
+    /// which players can't open as is. WAV audio is returned decoded as is.
+    ///
+    /// # Errors
+    /// `ErrorKind::InvalidInput` if the audio is neither PCM nor WAV, like MP3. Use `save`
+    /// for it. `ErrorKind::InvalidData` if `data` isn't valid base64.
+    pub fn to_wav(&self) -> Result<Vec<u8>, std::io::Error> {
+        let decode = || {
+            self.decode()
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+        };
+        match (
+            self.mime_type.type_().as_str(),
+            self.mime_type.subtype().as_str(),
+        ) {
+            ("audio", "l16") => {}
+            ("audio", "wav" | "x-wav" | "wave" | "vnd.wave") => return decode(),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("`{}` is not PCM audio", self.mime_type),
+                ));
+            }
+        }
+        let data = decode()?;
+        let channels = self
+            .mime_type
+            .get_param("channels")
+            .and_then(|channels| channels.as_str().parse().ok())
+            .unwrap_or(1);
+        pcm_to_wav(&data, self.get_sample_rate(), channels)
+    }
+    /// Writes `to_wav` to `path` with its extension replaced by "wav". Returns the path written.
+    ///
+    /// # Errors
+    /// Errors of `to_wav`, like for MP3 audio, and of writing the file.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub async fn save_wav(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<std::path::PathBuf, std::io::Error> {
+        let data = self.to_wav()?;
+        let path = path.as_ref().with_extension("wav");
+        tokio::fs::write(&path, data).await?;
+        Ok(path)
+    }
+    /// Decodes and writes `data` to `path` with its extension replaced by `get_extension`.
+    /// Returns the path written.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub async fn save(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<std::path::PathBuf, std::io::Error> {
+        let data = self
+            .decode()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        let path = path.as_ref().with_extension(self.get_extension());
+        tokio::fs::write(&path, data).await?;
+        Ok(path)
+    }
+}
+
+/// Wraps little endian 16 bit `pcm` samples in a WAV container.
+///
+/// # Errors
+/// `ErrorKind::InvalidInput` if `channels` is 0 or the header fields overflow, like for too
+/// many channels or more than 4 GiB of samples.
+pub fn pcm_to_wav(pcm: &[u8], sample_rate: u32, channels: u16) -> Result<Vec<u8>, std::io::Error> {
+    const BITS_PER_SAMPLE: u16 = 16;
+    let invalid =
+        |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string());
+    if channels == 0 {
+        return Err(invalid("WAV needs at least 1 channel"));
+    }
+    let block_align = channels
+        .checked_mul(BITS_PER_SAMPLE / 8)
+        .ok_or_else(|| invalid("too many channels for WAV"))?;
+    let byte_rate = sample_rate
+        .checked_mul(block_align.into())
+        .ok_or_else(|| invalid("byte rate too large for WAV"))?;
+    let (data_size, riff_size) = u32::try_from(pcm.len())
+        .ok()
+        .and_then(|data_size| Some((data_size, data_size.checked_add(36)?)))
+        .ok_or_else(|| invalid("audio too large for WAV"))?;
+    let mut wav = Vec::with_capacity(44 + pcm.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&riff_size.to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM format.
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&byte_rate.to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    wav.extend_from_slice(pcm);
+    Ok(wav)
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -379,11 +512,17 @@ impl<'a> From<&'a str> for SystemInstruction {
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
 pub enum HarmCategory {
     HarmCategoryHarassment,
     HarmCategoryHateSpeech,
     HarmCategorySexuallyExplicit,
     HarmCategoryDangerousContent,
+    /// Content that may be used to harm civic integrity, like about elections.
+    HarmCategoryCivicIntegrity,
+    /// Category not listed here, added to the API later. Can't be used in `SafetySetting`.
+    #[serde(other)]
+    Unknown,
 }
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -452,6 +591,25 @@ pub struct GeminiRequestBody<'a> {
     cached_content: Option<String>,
 }
 
+/// Body of [countTokens](https://ai.google.dev/api/tokens#method:-models.counttokens).
+/// Either `contents` alone or the complete request to generate content.
+#[derive(Serialize, new)]
+#[serde(rename_all = "camelCase")]
+pub struct CountTokensRequestBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contents: Option<&'a [&'a Chat]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generate_content_request: Option<GenerateContentRequest<'a>>,
+}
+
+#[derive(Serialize, new)]
+pub struct GenerateContentRequest<'a> {
+    /// Format: `models/{model}`
+    model: String,
+    #[serde(flatten)]
+    body: GeminiRequestBody<'a>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum Tool {
@@ -478,6 +636,12 @@ pub fn concatenate_parts(updating: &mut Vec<Part>, updator: &[Part]) {
                     if updating_last.is_thought() == updator_part.is_thought() {
                         if let PartType::Text(ref mut updating_text) = updating_last.data {
                             updating_text.push_str(&updator_text);
+                            // Signature comes with the last chunk of a part and must be sent
+                            // back for multi-turn image editing and function calling.
+                            if updator_part.thought_signature.is_some() {
+                                updating_last.thought_signature =
+                                    updator_part.thought_signature.clone();
+                            }
                             continue;
                         }
                     }
@@ -2,7 +2,7 @@ use derive_new::new;
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 
-use crate::gemini::types::request::{FunctionCall, Part, PartType, Role};
+use crate::gemini::types::request::{FunctionCall, InlineData, Part, PartType, Role};
 
 #[derive(Serialize, Deserialize, new, Getters, Debug, Clone)]
 pub struct Chat {
@@ -94,4 +94,60 @@ impl Chat {
             _ => None,
         })
     }
+    /// Images generated with `Modality::Image` in `responseModalities`. Intermediate images of
+    /// thinking are excluded.
+    pub fn get_images(&self) -> impl Iterator<Item = &InlineData> {
+        self.parts().iter().filter_map(|part| match part.data() {
+            PartType::InlineData(inline_data)
+                if !part.is_thought() && inline_data.mime_type().type_() == mime::IMAGE =>
+            {
+                Some(inline_data)
+            }
+            _ => None,
+        })
+    }
+    /// Audio generated with `Modality::Audio` in `responseModalities`, like speech of TTS
+    /// models. Streamed replies have the audio split in many parts. Audio of thinking is
+    /// excluded.
+    pub fn get_audios(&self) -> impl Iterator<Item = &InlineData> {
+        self.parts().iter().filter_map(|part| match part.data() {
+            PartType::InlineData(inline_data)
+                if !part.is_thought() && inline_data.mime_type().type_() == mime::AUDIO =>
+            {
+                Some(inline_data)
+            }
+            _ => None,
+        })
+    }
+    /// Writes the images of `get_images` to `{path_prefix}_{n}.{extension}`, `n` counting from
+    /// 0. Returns the paths written.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini) {
+    /// use gemini_client_api::gemini::types::response::Modality;
+    /// use gemini_client_api::gemini::types::sessions::Session;
+    /// let gemini = gemini.set_response_modalities(vec![Modality::Text, Modality::Image]);
+    /// let mut session = Session::new(6);
+    /// session.ask("Draw a cat wearing a hat");
+    /// let response = gemini.ask(&mut session).await.unwrap();
+    /// let paths = response.get_chat().save_images("cat").await.unwrap();
+    /// // Edit the image in the same session.
+    /// session.ask("Make the hat red");
+    /// let response = gemini.ask(&mut session).await.unwrap();
+    /// response.get_chat().save_images("red_hat_cat").await.unwrap();
+    /// # }
+    /// ```
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub async fn save_images(
+        &self,
+        path_prefix: impl AsRef<str>,
+    ) -> Result<Vec<std::path::PathBuf>, std::io::Error> {
+        let mut paths = Vec::new();
+        for (n, image) in self.get_images().enumerate() {
+            let path = format!("{}_{n}.{}", path_prefix.as_ref(), image.get_extension());
+            paths.push(image.save(path).await?);
+        }
+        Ok(paths)
+    }
 }
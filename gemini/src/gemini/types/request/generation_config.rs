@@ -0,0 +1,300 @@
+use super::ThinkingConfig;
+use crate::gemini::types::response::Modality;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MediaResolution {
+    MediaResolutionUnspecified,
+    /// 64 tokens.
+    MediaResolutionLow,
+    /// 256 tokens.
+    MediaResolutionMedium,
+    /// Zoomed reframing with 256 tokens.
+    MediaResolutionHigh,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrebuiltVoiceConfig {
+    /// Name of a [voice](https://ai.google.dev/gemini-api/docs/speech-generation#voices) like "Kore".
+    pub voice_name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceConfig {
+    pub prebuilt_voice_config: PrebuiltVoiceConfig,
+}
+impl VoiceConfig {
+    pub fn new(voice_name: impl Into<String>) -> Self {
+        Self {
+            prebuilt_voice_config: PrebuiltVoiceConfig {
+                voice_name: voice_name.into(),
+            },
+        }
+    }
+}
+
+/// Voice of a speaker named in the prompt, like "Joe: How's it going today?".
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerVoiceConfig {
+    pub speaker: String,
+    pub voice_config: VoiceConfig,
+}
+impl SpeakerVoiceConfig {
+    pub fn new(speaker: impl Into<String>, voice_name: impl Into<String>) -> Self {
+        Self {
+            speaker: speaker.into(),
+            voice_config: VoiceConfig::new(voice_name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSpeakerVoiceConfig {
+    /// Upto 2 speakers.
+    pub speaker_voice_configs: Vec<SpeakerVoiceConfig>,
+}
+
+/// Voice of generated speech when `responseModalities` is `[Modality::Audio]`.
+/// Either `voice_config` or `multi_speaker_voice_config` should be set.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::types::request::SpeechConfig;
+/// let narrator = SpeechConfig::new("Kore");
+/// let dialogue = SpeechConfig::new_multi_speaker([("Joe", "Kore"), ("Jane", "Puck")]);
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeechConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice_config: Option<VoiceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multi_speaker_voice_config: Option<MultiSpeakerVoiceConfig>,
+    /// BCP 47 language code like "en-US".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_code: Option<String>,
+}
+impl SpeechConfig {
+    pub fn new(voice_name: impl Into<String>) -> Self {
+        Self {
+            voice_config: Some(VoiceConfig::new(voice_name)),
+            ..Default::default()
+        }
+    }
+    /// `speakers` are pairs of speaker name, as used in the prompt, and voice name.
+    pub fn new_multi_speaker<S, V>(speakers: impl IntoIterator<Item = (S, V)>) -> Self
+    where
+        S: Into<String>,
+        V: Into<String>,
+    {
+        let speaker_voice_configs = speakers
+            .into_iter()
+            .map(|(speaker, voice_name)| SpeakerVoiceConfig::new(speaker, voice_name))
+            .collect();
+        Self {
+            multi_speaker_voice_config: Some(MultiSpeakerVoiceConfig {
+                speaker_voice_configs,
+            }),
+            ..Default::default()
+        }
+    }
+    pub fn set_language_code(mut self, language_code: impl Into<String>) -> Self {
+        self.language_code = Some(language_code.into());
+        self
+    }
+}
+
+/// Settings of images generated when `responseModalities` has `Modality::Image`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageConfig {
+    /// Aspect ratio like "1:1", "16:9" or "9:16".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<String>,
+    /// Resolution like "1K", "2K" or "4K". Supported by some models only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_size: Option<String>,
+}
+impl ImageConfig {
+    pub fn set_aspect_ratio(mut self, aspect_ratio: impl Into<String>) -> Self {
+        self.aspect_ratio = Some(aspect_ratio.into());
+        self
+    }
+    pub fn set_image_size(mut self, image_size: impl Into<String>) -> Self {
+        self.image_size = Some(image_size.into());
+        self
+    }
+}
+
+/// Typed [generation config](https://ai.google.dev/api/generate-content#generationconfig).
+/// Only the fields set are sent, leaving the rest to model defaults.
+///
+/// Applied by `Gemini::merge_generation_config`, which keeps the fields set before, like by
+/// `set_thinking_config` or `set_json_mode`. Fields not covered here can still be set through
+/// `Gemini::set_generation_config`.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::types::request::GenerationConfig;
+/// let config = GenerationConfig::default()
+///     .set_temperature(0.2)
+///     .set_max_output_tokens(1024)
+///     .set_stop_sequences(vec!["END".into()]);
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidate_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_logprobs: Option<bool>,
+    /// Count of top logprobs returned at each step. Needs `response_logprobs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    /// Schema in the [OpenAPI subset](https://ai.google.dev/api/caching#Schema) format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<Value>,
+    /// Schema in the [JSON Schema](https://json-schema.org/) format. Alternative to `response_schema`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_json_schema: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_modalities: Option<Vec<Modality>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_config: Option<ImageConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_resolution: Option<MediaResolution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speech_config: Option<SpeechConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_config: Option<ThinkingConfig>,
+}
+impl GenerationConfig {
+    pub fn set_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+    pub fn set_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+    pub fn set_top_k(mut self, top_k: u32) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+    /// Count of candidates to generate. See `CandidateSelector`.
+    pub fn set_candidate_count(mut self, candidate_count: u32) -> Self {
+        self.candidate_count = Some(candidate_count);
+        self
+    }
+    pub fn set_max_output_tokens(mut self, max_output_tokens: u32) -> Self {
+        self.max_output_tokens = Some(max_output_tokens);
+        self
+    }
+    /// Upto 5 sequences that stop generation.
+    pub fn set_stop_sequences(mut self, stop_sequences: Vec<String>) -> Self {
+        self.stop_sequences = Some(stop_sequences);
+        self
+    }
+    pub fn set_seed(mut self, seed: i32) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    pub fn set_presence_penalty(mut self, presence_penalty: f32) -> Self {
+        self.presence_penalty = Some(presence_penalty);
+        self
+    }
+    pub fn set_frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+        self.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+    pub fn set_response_logprobs(mut self, response_logprobs: bool) -> Self {
+        self.response_logprobs = Some(response_logprobs);
+        self
+    }
+    pub fn set_logprobs(mut self, logprobs: u32) -> Self {
+        self.logprobs = Some(logprobs);
+        self
+    }
+    pub fn set_response_mime_type(mut self, response_mime_type: impl Into<String>) -> Self {
+        self.response_mime_type = Some(response_mime_type.into());
+        self
+    }
+    pub fn set_response_schema(mut self, response_schema: Value) -> Self {
+        self.response_schema = Some(response_schema);
+        self
+    }
+    pub fn set_response_json_schema(mut self, response_json_schema: Value) -> Self {
+        self.response_json_schema = Some(response_json_schema);
+        self
+    }
+    /// Kinds of output, like `[Modality::Text, Modality::Image]` for image models.
+    pub fn set_response_modalities(mut self, response_modalities: Vec<Modality>) -> Self {
+        self.response_modalities = Some(response_modalities);
+        self
+    }
+    pub fn set_image_config(mut self, image_config: ImageConfig) -> Self {
+        self.image_config = Some(image_config);
+        self
+    }
+    pub fn set_media_resolution(mut self, media_resolution: MediaResolution) -> Self {
+        self.media_resolution = Some(media_resolution);
+        self
+    }
+    pub fn set_speech_config(mut self, speech_config: SpeechConfig) -> Self {
+        self.speech_config = Some(speech_config);
+        self
+    }
+    pub fn set_thinking_config(mut self, thinking_config: ThinkingConfig) -> Self {
+        self.thinking_config = Some(thinking_config);
+        self
+    }
+    /// Writes the fields set into `config`, replacing the same fields set before in either
+    /// camelCase or snake_case.
+    pub(crate) fn merge_into(&self, config: &mut Map<String, Value>) {
+        let Ok(Value::Object(fields)) = serde_json::to_value(self) else {
+            return;
+        };
+        for (key, value) in fields {
+            config.remove(&to_snake_case(&key));
+            config.insert(key, value);
+        }
+    }
+}
+
+/// "responseMimeType" to "response_mime_type"
+pub(crate) fn to_snake_case(camel_case: &str) -> String {
+    let mut snake_case = String::with_capacity(camel_case.len() + 4);
+    for char in camel_case.chars() {
+        if char.is_ascii_uppercase() {
+            snake_case.push('_');
+            snake_case.push(char.to_ascii_lowercase());
+        } else {
+            snake_case.push(char);
+        }
+    }
+    snake_case
+}
@@ -1,6 +1,8 @@
 use super::request::*;
 use super::sessions::Session;
 #[cfg(feature = "reqwest")]
+use crate::gemini::ask::RateLimitPermit;
+#[cfg(feature = "reqwest")]
 use crate::gemini::error::GeminiResponseStreamError;
 use bytes::Bytes;
 use derive_new::new;
@@ -11,9 +13,17 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
+mod grounding;
+pub use grounding::{
+    CitationMetadata, CitationSource, GroundingChunk, GroundingMetadata, GroundingSupport,
+    RetrievedContextChunk, SearchEntryPoint, Segment, UrlContextMetadata, UrlMetadata,
+    UrlRetrievalStatus, WebChunk,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum FinishReason {
@@ -43,22 +53,372 @@ pub enum FinishReason {
     ImageSafety,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HarmProbability {
+    /// Probability is unspecified.
+    HarmProbabilityUnspecified,
+    /// Content has a negligible chance of being unsafe.
+    Negligible,
+    /// Content has a low chance of being unsafe.
+    Low,
+    /// Content has a medium chance of being unsafe.
+    Medium,
+    /// Content has a high chance of being unsafe.
+    High,
+    /// Probability not listed here, added to the API later. Ordered above `High` to be on the
+    /// safe side.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SafetyRating {
+    pub category: HarmCategory,
+    pub probability: HarmProbability,
+    /// If true, the content was blocked because of this rating.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BlockReason {
+    /// Default value. This value is unused.
+    BlockReasonUnspecified,
+    /// Prompt was blocked for safety reasons. See `PromptFeedback::safety_ratings`.
+    Safety,
+    /// Prompt was blocked for unknown reasons.
+    Other,
+    /// Prompt was blocked because it contains terms of the terminology blocklist.
+    Blocklist,
+    /// Prompt was blocked for prohibited content.
+    ProhibitedContent,
+    /// Candidates were blocked for unsafe image generation content.
+    ImageSafety,
+    /// Reason not listed here, added to the API later.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Feedback on the prompt. `block_reason` is set if the prompt was blocked, in which case
+/// there are no candidates.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptFeedback {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_reason: Option<BlockReason>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub safety_ratings: Vec<SafetyRating>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Modality {
+    /// Unspecified modality.
+    ModalityUnspecified,
+    /// Plain text.
+    Text,
+    /// Image.
+    Image,
+    /// Video.
+    Video,
+    /// Audio.
+    Audio,
+    /// Document, e.g. PDF.
+    Document,
+    /// Modality not listed here, added to the API later.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModalityTokenCount {
+    pub modality: Modality,
+    #[serde(default)]
+    pub token_count: u32,
+}
+
+/// Token counts of a request and its reply.
+///
+/// See [Gemini docs](https://ai.google.dev/api/generate-content#UsageMetadata)
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+    /// Tokens in the prompt. Includes `cached_content_token_count` when cached content is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_token_count: Option<u32>,
+    /// Tokens in all the generated candidates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates_token_count: Option<u32>,
+    /// Tokens of the prompt read from cached content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_content_token_count: Option<u32>,
+    /// Tokens spent in thinking.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thoughts_token_count: Option<u32>,
+    /// Tokens in the results of tool use, like Google search, fed back to the model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_use_prompt_token_count: Option<u32>,
+    /// Total tokens of the request and reply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_token_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_tokens_details: Vec<ModalityTokenCount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_tokens_details: Vec<ModalityTokenCount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates_tokens_details: Vec<ModalityTokenCount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_use_prompt_tokens_details: Vec<ModalityTokenCount>,
+}
+impl UsageMetadata {
+    /// Updates with usage of a later chunk of the same streamed reply.
+    /// Gemini reports running totals in every chunk, so counts present in `latest` replace
+    /// the older ones.
+    pub fn update(&mut self, latest: &UsageMetadata) {
+        fn replace<T: Clone>(older: &mut Option<T>, latest: &Option<T>) {
+            if latest.is_some() {
+                older.clone_from(latest);
+            }
+        }
+        fn replace_details(older: &mut Vec<ModalityTokenCount>, latest: &[ModalityTokenCount]) {
+            if !latest.is_empty() {
+                *older = latest.to_vec();
+            }
+        }
+        replace(&mut self.prompt_token_count, &latest.prompt_token_count);
+        replace(
+            &mut self.candidates_token_count,
+            &latest.candidates_token_count,
+        );
+        replace(
+            &mut self.cached_content_token_count,
+            &latest.cached_content_token_count,
+        );
+        replace(&mut self.thoughts_token_count, &latest.thoughts_token_count);
+        replace(
+            &mut self.tool_use_prompt_token_count,
+            &latest.tool_use_prompt_token_count,
+        );
+        replace(&mut self.total_token_count, &latest.total_token_count);
+        replace_details(
+            &mut self.prompt_tokens_details,
+            &latest.prompt_tokens_details,
+        );
+        replace_details(&mut self.cache_tokens_details, &latest.cache_tokens_details);
+        replace_details(
+            &mut self.candidates_tokens_details,
+            &latest.candidates_tokens_details,
+        );
+        replace_details(
+            &mut self.tool_use_prompt_tokens_details,
+            &latest.tool_use_prompt_tokens_details,
+        );
+    }
+}
+
+/// Reply of [countTokens](https://ai.google.dev/api/tokens#method:-models.counttokens).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CountTokensResponse {
+    /// Tokens the prompt is tokenized into, including cached content.
+    #[serde(default)]
+    pub total_tokens: u32,
+    /// Tokens of the prompt read from cached content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_content_token_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_tokens_details: Vec<ModalityTokenCount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_tokens_details: Vec<ModalityTokenCount>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, new)]
 #[serde(rename_all = "camelCase")]
 pub struct Candidate {
+    /// Empty if the candidate was blocked before generating anything.
+    #[serde(default = "empty_reply")]
     pub content: Chat,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub finish_reason: Option<FinishReason>,
+    /// Index of the candidate among `candidateCount` candidates. Omitted for the first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    pub index: Option<u32>,
+    /// Sources of the reply when grounded by `Tool::GoogleSearch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    pub grounding_metadata: Option<GroundingMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    pub citation_metadata: Option<CitationMetadata>,
+    /// URLs read when `Tool::UrlContext` is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    pub url_context_metadata: Option<UrlContextMetadata>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[new(default)]
+    pub safety_ratings: Vec<SafetyRating>,
+}
+impl Candidate {
+    pub fn get_index(&self) -> u32 {
+        self.index.unwrap_or(0)
+    }
+    /// If true, generation of this candidate was stopped for safety, recitation or
+    /// prohibited content.
+    pub fn is_blocked(&self) -> bool {
+        matches!(
+            self.finish_reason,
+            Some(
+                FinishReason::Safety
+                    | FinishReason::Recitation
+                    | FinishReason::Blocklist
+                    | FinishReason::ProhibitedContent
+                    | FinishReason::Spii
+                    | FinishReason::ImageSafety
+            )
+        )
+    }
+    /// Same as `Chat::get_text_no_think` but with markers like "[1][3]" after each grounded
+    /// segment. Marker `n` refers to `grounding_metadata.grounding_chunks[n - 1]`.
+    pub fn get_text_with_citations(&self, seperator: impl AsRef<str>) -> String {
+        let supports = self
+            .grounding_metadata
+            .as_ref()
+            .map(|metadata| metadata.grounding_supports.as_slice())
+            .unwrap_or_default();
+        self.content
+            .parts()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, part)| match part.data() {
+                PartType::Text(text) if !part.is_thought() => {
+                    let mut markers: Vec<(usize, String)> = supports
+                        .iter()
+                        .filter(|support| support.segment.part_index == index)
+                        .filter(|support| !support.grounding_chunk_indices.is_empty())
+                        .map(|support| {
+                            let mut end = support.segment.end_index.min(text.len());
+                            while !text.is_char_boundary(end) {
+                                end -= 1;
+                            }
+                            let marker = support
+                                .grounding_chunk_indices
+                                .iter()
+                                .map(|chunk| format!("[{}]", chunk + 1))
+                                .collect::<String>();
+                            (end, marker)
+                        })
+                        .collect();
+                    // Inserting from the end keeps the earlier offsets valid.
+                    markers.sort_by_key(|(end, _)| std::cmp::Reverse(*end));
+                    let mut text = text.to_string();
+                    for (end, marker) in markers {
+                        text.insert_str(end, &marker);
+                    }
+                    Some(text)
+                }
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join(seperator.as_ref())
+    }
+    /// Appends `chunk`, a streamed part of this candidate.
+    pub(crate) fn extend(&mut self, chunk: &Candidate) {
+        concatenate_parts(self.content.parts_mut(), chunk.content.parts());
+        if chunk.finish_reason.is_some() {
+            self.finish_reason = chunk.finish_reason.clone();
+        }
+        if chunk.grounding_metadata.is_some() {
+            self.grounding_metadata = chunk.grounding_metadata.clone();
+        }
+        if chunk.citation_metadata.is_some() {
+            self.citation_metadata = chunk.citation_metadata.clone();
+        }
+        if chunk.url_context_metadata.is_some() {
+            self.url_context_metadata = chunk.url_context_metadata.clone();
+        }
+        if !chunk.safety_ratings.is_empty() {
+            self.safety_ratings = chunk.safety_ratings.clone();
+        }
+    }
+}
+
+fn empty_reply() -> Chat {
+    Chat::new(Role::Model, Vec::new())
+}
+
+pub type CandidateSelectorFn = dyn Fn(&[Candidate]) -> usize + Send + Sync;
+
+/// Decides which candidate of a reply with many candidates (`candidateCount` in generation
+/// config) is stored in the `Session`. Candidates are passed sorted by index.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::types::response::CandidateSelector;
+/// use gemini_client_api::gemini::types::sessions::Session;
+/// let session = Session::new(6).set_candidate_selector(CandidateSelector::Longest);
+/// ```
+#[derive(Clone, Default)]
+pub enum CandidateSelector {
+    /// The candidate of index 0.
+    #[default]
+    First,
+    /// The candidate with the most characters of text.
+    Longest,
+    /// The first candidate not `Candidate::is_blocked`, else the first.
+    FirstNotBlocked,
+    /// Returns the position in the slice of the candidate to store.
+    Custom(Arc<CandidateSelectorFn>),
+}
+impl CandidateSelector {
+    /// Position in `candidates` of the selected candidate. `None` if `candidates` is empty.
+    pub fn select(&self, candidates: &[Candidate]) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+        let position = match self {
+            CandidateSelector::First => 0,
+            CandidateSelector::Longest => candidates
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, candidate)| candidate.content.get_text_no_think("").len())
+                .map_or(0, |(position, _)| position),
+            CandidateSelector::FirstNotBlocked => candidates
+                .iter()
+                .position(|candidate| !candidate.is_blocked())
+                .unwrap_or(0),
+            CandidateSelector::Custom(selector) => selector(candidates),
+        };
+        Some(position.min(candidates.len() - 1))
+    }
+}
+impl std::fmt::Debug for CandidateSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CandidateSelector::First => write!(f, "First"),
+            CandidateSelector::Longest => write!(f, "Longest"),
+            CandidateSelector::FirstNotBlocked => write!(f, "FirstNotBlocked"),
+            CandidateSelector::Custom(_) => write!(f, "Custom"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GeminiResponse {
+    /// Empty if the prompt was blocked. See `prompt_feedback`.
+    #[serde(default)]
     pub candidates: Vec<Candidate>,
-    pub usage_metadata: Value,
+    #[serde(default)]
+    pub usage_metadata: UsageMetadata,
     pub model_version: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub prompt_feedback: Option<Value>,
+    pub prompt_feedback: Option<PromptFeedback>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -72,11 +432,46 @@ impl GeminiResponse {
     pub(crate) fn from_str(string: impl AsRef<str>) -> Result<Self, serde_json::Error> {
         serde_json::from_str(string.as_ref())
     }
+    /// Chat of the first candidate. Empty if there is no candidate, like when the prompt
+    /// was blocked.
     pub fn get_chat(&self) -> &Chat {
-        &self.candidates[0].content
+        static EMPTY_REPLY: std::sync::LazyLock<Chat> = std::sync::LazyLock::new(empty_reply);
+        match self.candidates.first() {
+            Some(candidate) => &candidate.content,
+            None => &EMPTY_REPLY,
+        }
     }
     pub fn get_finish_reason(&self) -> Option<&FinishReason> {
-        self.candidates[0].finish_reason.as_ref()
+        self.candidates.first()?.finish_reason.as_ref()
+    }
+    pub fn get_prompt_feedback(&self) -> Option<&PromptFeedback> {
+        self.prompt_feedback.as_ref()
+    }
+    /// Reason the prompt was blocked, if it was.
+    pub fn get_block_reason(&self) -> Option<&BlockReason> {
+        self.prompt_feedback.as_ref()?.block_reason.as_ref()
+    }
+    /// If true, the prompt was blocked or every candidate was blocked, so there is no reply
+    /// to use. A response without candidates and block reason isn't blocked.
+    pub fn is_blocked(&self) -> bool {
+        self.get_block_reason().is_some()
+            || (!self.candidates.is_empty() && self.candidates.iter().all(Candidate::is_blocked))
+    }
+    pub fn get_candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+    /// Candidate of `index`, as in `Candidate::get_index`.
+    pub fn get_candidate(&self, index: u32) -> Option<&Candidate> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.get_index() == index)
+    }
+    /// Chats of all the candidates.
+    pub fn get_chats(&self) -> Vec<&Chat> {
+        self.candidates
+            .iter()
+            .map(|candidate| &candidate.content)
+            .collect()
     }
     pub fn get_json<T>(&self) -> Result<T, serde_json::Error>
     where
@@ -102,6 +497,9 @@ pin_project_lite::pin_project! {
         session: Session,
         data_extractor: F,
         buffer: Vec<u8>,
+        rate_limit_permit: Option<RateLimitPermit>,
+        usage_metadata: UsageMetadata,
+        candidates: Vec<Candidate>,
     }
 }
 #[cfg(feature = "reqwest")]
@@ -144,8 +542,27 @@ where
                             }
                         };
 
+                        this.usage_metadata.update(&response.usage_metadata);
+                        if let Some(permit) = this.rate_limit_permit {
+                            permit.set_pending_tokens(
+                                this.usage_metadata.total_token_count.unwrap_or(0).into(),
+                            );
+                        }
+                        for chunk in &response.candidates {
+                            match this
+                                .candidates
+                                .iter_mut()
+                                .find(|candidate| candidate.get_index() == chunk.get_index())
+                            {
+                                Some(candidate) => candidate.extend(chunk),
+                                None => {
+                                    this.candidates.push(chunk.clone());
+                                    this.candidates.sort_by_key(Candidate::get_index);
+                                }
+                            }
+                        }
                         // Update the session and return the data.
-                        this.session.update(&response);
+                        this.session.update_stream(&response);
                         let data = (this.data_extractor)(this.session, response);
                         return Poll::Ready(Some(Ok(data)));
                     }
@@ -162,6 +579,11 @@ where
                     return Poll::Pending;
                 }
                 Poll::Ready(None) => {
+                    if this.candidates.len() > 1 {
+                        this.session.select_streamed_candidate(this.candidates);
+                    }
+                    // The permit records the tokens spent when dropped.
+                    this.rate_limit_permit.take();
                     if this.buffer.is_empty() {
                         return Poll::Ready(None);
                     } else {
@@ -194,14 +616,29 @@ where
         >,
         session: Session,
         data_extractor: F,
+        rate_limit_permit: Option<RateLimitPermit>,
     ) -> Self {
         Self {
             response_stream,
             session,
             data_extractor,
             buffer: Vec::new(),
+            rate_limit_permit,
+            usage_metadata: UsageMetadata::default(),
+            candidates: Vec::new(),
         }
     }
+    /// Candidates received so far, each accumulated from the chunks of its index and sorted
+    /// by index. Once the stream is exhausted, the one chosen by the `CandidateSelector` of the
+    /// session is stored in the session.
+    pub fn get_candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+    /// Token usage of the reply received so far. It is the usage of the complete reply once
+    /// the stream is exhausted.
+    pub fn get_usage_metadata(&self) -> &UsageMetadata {
+        &self.usage_metadata
+    }
     pub fn get_session(&self) -> &Session {
         &self.session
     }
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Google Search suggestions to display with grounded replies, as required by the
+/// [terms](https://ai.google.dev/gemini-api/docs/grounding/search-suggestions).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEntryPoint {
+    /// HTML and CSS snippet to embed in a web page or web view.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered_content: Option<String>,
+    /// Base64 encoded JSON of search terms and URLs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sdk_blob: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebChunk {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievedContextChunk {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// A source the reply is grounded on.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GroundingChunk {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web: Option<WebChunk>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieved_context: Option<RetrievedContextChunk>,
+}
+impl GroundingChunk {
+    pub fn get_uri(&self) -> Option<&str> {
+        match (&self.web, &self.retrieved_context) {
+            (Some(web), _) => web.uri.as_deref(),
+            (None, Some(context)) => context.uri.as_deref(),
+            (None, None) => None,
+        }
+    }
+    pub fn get_title(&self) -> Option<&str> {
+        match (&self.web, &self.retrieved_context) {
+            (Some(web), _) => web.title.as_deref(),
+            (None, Some(context)) => context.title.as_deref(),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Span of the text of a part. Indices are in bytes of its UTF-8 encoding.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    #[serde(default)]
+    pub part_index: usize,
+    /// Inclusive.
+    #[serde(default)]
+    pub start_index: usize,
+    /// Exclusive.
+    #[serde(default)]
+    pub end_index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Links a `Segment` of the reply to the `GroundingChunk`s supporting it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GroundingSupport {
+    #[serde(default)]
+    pub segment: Segment,
+    /// Indices into `GroundingMetadata::grounding_chunks`.
+    #[serde(default)]
+    pub grounding_chunk_indices: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confidence_scores: Vec<f32>,
+}
+
+/// Sources of a reply grounded with `Tool::GoogleSearch` or retrieval.
+/// See [grounding](https://ai.google.dev/gemini-api/docs/google-search).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GroundingMetadata {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grounding_chunks: Vec<GroundingChunk>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grounding_supports: Vec<GroundingSupport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub web_search_queries: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_entry_point: Option<SearchEntryPoint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_metadata: Option<Value>,
+}
+
+/// A source recited by the reply.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationSource {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationMetadata {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citation_sources: Vec<CitationSource>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UrlRetrievalStatus {
+    UrlRetrievalStatusUnspecified,
+    UrlRetrievalStatusSuccess,
+    UrlRetrievalStatusError,
+    /// The content is behind a paywall.
+    UrlRetrievalStatusPaywall,
+    /// The content is unsafe.
+    UrlRetrievalStatusUnsafe,
+    /// Status not listed here, added to the API later.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlMetadata {
+    pub retrieved_url: String,
+    pub url_retrieval_status: UrlRetrievalStatus,
+}
+
+/// URLs read by `Tool::UrlContext`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlContextMetadata {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub url_metadata: Vec<UrlMetadata>,
+}
@@ -1,9 +1,12 @@
 use super::request::*;
-use super::response::GeminiResponse;
+use super::response::{Candidate, CandidateSelector, GeminiResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::VecDeque;
+use std::sync::Arc;
 use std::{usize, vec};
+mod token_counter;
+pub use token_counter::{HeuristicTokenCounter, TokenCounter};
 
 #[derive(thiserror::Error, Debug)]
 pub enum AddFunctionResponseError {
@@ -18,13 +21,21 @@ pub enum AddFunctionResponseError {
 /// Manages the conversation history and configuration for a Gemini session.
 ///
 /// A `Session` tracks the sequence of `Chat` messages (user prompts and model replies)
-/// and enforces a history limit to manage token usage.
+/// and enforces a history limit, by count of chats and optionally by tokens, to manage token usage.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Session {
     history: VecDeque<Chat>,
     history_limit: usize,
     chat_no: usize,
     remember_reply: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token_limit: Option<u32>,
+    #[serde(skip)]
+    token_counter: Option<Arc<dyn TokenCounter>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    archive: Vec<Chat>,
+    #[serde(skip)]
+    candidate_selector: CandidateSelector,
 }
 impl Session {
     /// Creates a new `Session` with a specified history limit.
@@ -43,8 +54,55 @@ impl Session {
             history_limit,
             chat_no: 0,
             remember_reply: true,
+            token_limit: None,
+            token_counter: None,
+            archive: Vec::new(),
+            candidate_selector: CandidateSelector::First,
         }
     }
+    /// Limits history by estimated tokens along with the `history_limit` on count of `Chat`.
+    /// Oldest chats are popped until the history fits `token_limit`, but the latest `Chat` is
+    /// always kept even if it alone exceeds the limit. A latest function response is kept along
+    /// with its function call and the prompt before it, as history can't start with them.
+    ///
+    /// Tokens are estimated by the counter set by `set_token_counter`, else by
+    /// `HeuristicTokenCounter::default()`.
+    pub fn set_token_limit(mut self, token_limit: Option<u32>) -> Self {
+        self.token_limit = token_limit;
+        self
+    }
+    /// # Note
+    /// The counter is not serialized. Set it again after deserializing the session.
+    pub fn set_token_counter(mut self, token_counter: impl TokenCounter + 'static) -> Self {
+        self.token_counter = Some(Arc::new(token_counter));
+        self
+    }
+    pub fn get_token_limit(&self) -> Option<u32> {
+        self.token_limit
+    }
+    /// Estimated tokens of the `chat` by the session's counter.
+    pub fn count_chat_tokens(&self, chat: &Chat) -> u32 {
+        match &self.token_counter {
+            Some(counter) => counter.count_tokens(chat),
+            None => HeuristicTokenCounter::default().count_tokens(chat),
+        }
+    }
+    /// Estimated tokens of the whole history by the session's counter.
+    pub fn count_history_tokens(&self) -> u32 {
+        self.history
+            .iter()
+            .map(|chat| self.count_chat_tokens(chat))
+            .sum()
+    }
+    /// Decides which candidate of replies with many candidates is stored in history.
+    /// `CandidateSelector::First` by default.
+    pub fn set_candidate_selector(mut self, candidate_selector: CandidateSelector) -> Self {
+        self.candidate_selector = candidate_selector;
+        self
+    }
+    pub fn get_candidate_selector(&self) -> &CandidateSelector {
+        &self.candidate_selector
+    }
     ///Set to false to stop automatic context storing
     pub fn set_remember_reply(mut self, remember: bool) -> Self {
         self.remember_reply = remember;
@@ -56,7 +114,7 @@ impl Session {
     pub fn get_history_as_vecdeque(&self) -> &VecDeque<Chat> {
         &self.history
     }
-    pub(super) fn get_history_as_vecdeque_mut(&mut self) -> &mut VecDeque<Chat> {
+    pub(crate) fn get_history_as_vecdeque_mut(&mut self) -> &mut VecDeque<Chat> {
         &mut self.history
     }
     /// Count of all the chats of any role.
@@ -133,16 +191,50 @@ impl Session {
         self.chat_no += 1;
         if self.get_history_length() > self.get_history_limit() {
             self.history.pop_front();
-            while let Some(front_chat) = self.history.front() {
-                match front_chat.role() {
-                    Role::Function => self.history.pop_front(),
-                    Role::Model if front_chat.has_function_call() => self.history.pop_front(),
-                    _ => break,
+            self.pop_invalid_front();
+        }
+        if let Some(token_limit) = self.token_limit {
+            let mut tokens = self.count_history_tokens();
+            while tokens > token_limit {
+                let Some(popped_tokens) = self.pop_oldest() else {
+                    break;
                 };
+                tokens = tokens.saturating_sub(popped_tokens);
             }
         }
         Ok(self)
     }
+    /// History must not start with a function call or its response.
+    fn is_valid_front(chat: &Chat) -> bool {
+        match chat.role() {
+            Role::Function => false,
+            Role::Model => !chat.has_function_call(),
+            _ => true,
+        }
+    }
+    fn pop_invalid_front(&mut self) {
+        while let Some(front_chat) = self.history.front() {
+            if Self::is_valid_front(front_chat) {
+                break;
+            }
+            self.history.pop_front();
+        }
+    }
+    /// Pops the oldest chat along with the function calls and responses it leaves at the front.
+    /// Chats from the last valid front onwards are never popped, so the latest chat is always
+    /// kept with the function call it responds to.
+    ///
+    /// # Returns
+    /// Estimated tokens popped, or `None` if nothing can be popped.
+    fn pop_oldest(&mut self) -> Option<u32> {
+        let keep_from = self.history.iter().rposition(Self::is_valid_front)?;
+        if keep_from == 0 {
+            return None;
+        }
+        let end = self.history.iter().skip(1).position(Self::is_valid_front)? + 1;
+        let popped: Vec<Chat> = self.history.drain(..end).collect();
+        Some(popped.iter().map(|chat| self.count_chat_tokens(chat)).sum())
+    }
     /// If `ask` is called more than once without passing through `gemini.ask(&mut session)`
     /// or `session.reply("ok")`, the parts is concatenated with the previous parts.
     pub fn ask_parts(&mut self, parts: Vec<Part>) -> &mut Self {
@@ -191,8 +283,26 @@ impl Session {
         Ok(self)
     }
     pub(crate) fn update<'b>(&mut self, response: &'b GeminiResponse) -> Option<&'b Vec<Part>> {
+        let position = self
+            .candidate_selector
+            .select(response.get_candidates())
+            .unwrap_or(0);
+        let candidate = response.get_candidates().get(position)?;
+        self.update_with(candidate.content.parts())
+    }
+    /// Stores a chunk of a streamed reply. The candidate of index 0 is stored while streaming
+    /// and is replaced by `select_streamed_candidate` at the end if another one is selected.
+    pub(crate) fn update_stream<'b>(
+        &mut self,
+        response: &'b GeminiResponse,
+    ) -> Option<&'b Vec<Part>> {
+        match response.get_candidate(0) {
+            Some(candidate) => self.update_with(candidate.content.parts()),
+            None => None,
+        }
+    }
+    fn update_with<'b>(&mut self, reply_parts: &'b Vec<Part>) -> Option<&'b Vec<Part>> {
         if self.get_remember_reply() {
-            let reply_parts = response.get_chat().parts();
             self.reply_parts(reply_parts.clone());
             Some(reply_parts)
         } else {
@@ -204,6 +314,27 @@ impl Session {
             None
         }
     }
+    /// Replaces the streamed reply by the candidate selected from complete `candidates`.
+    pub(crate) fn select_streamed_candidate(&mut self, candidates: &[Candidate]) {
+        if !self.get_remember_reply() {
+            return;
+        }
+        let Some(position) = self.candidate_selector.select(candidates) else {
+            return;
+        };
+        let selected = &candidates[position];
+        if selected.get_index() == 0 {
+            return;
+        }
+        match self.get_last_chat_mut() {
+            Some(chat) if *chat.role() == Role::Model => {
+                *chat.parts_mut() = selected.content.parts().clone();
+            }
+            _ => {
+                self.reply_parts(selected.content.parts().clone());
+            }
+        }
+    }
     pub fn get_last_chat(&self) -> Option<&Chat> {
         self.get_history_as_vecdeque().back()
     }
@@ -226,4 +357,51 @@ impl Session {
     pub fn remove_last_chat(&mut self) -> Option<Chat> {
         self.history.pop_back()
     }
+    /// Count of the oldest chats to remove to remove at least `chat_count` chats such that the
+    /// remaining history starts with a `Role::User` prompt.
+    /// Returns `None` if no such prompt is left after them.
+    pub fn get_compactable_chat_count(&self, chat_count: usize) -> Option<usize> {
+        let count = chat_count.max(1);
+        self.history
+            .iter()
+            .enumerate()
+            .skip(count)
+            .find(|(_, chat)| *chat.role() == Role::User)
+            .map(|(index, _)| index)
+    }
+    /// Replaces the oldest `chat_count` chats with a synthetic first exchange: a user prompt
+    /// containing `summary` and a model acknowledgement.
+    ///
+    /// `chat_count` should be from `get_compactable_chat_count` so that the history stays
+    /// valid. If `archive` is true, the replaced chats are appended to `get_archive()`.
+    /// # Returns
+    /// The replaced chats.
+    pub fn compact(
+        &mut self,
+        chat_count: usize,
+        summary: impl AsRef<str>,
+        archive: bool,
+    ) -> Vec<Chat> {
+        let chat_count = chat_count.min(self.get_history_length());
+        let replaced: Vec<Chat> = self.history.drain(..chat_count).collect();
+        self.history.push_front(Chat::new(
+            Role::Model,
+            vec!["Understood. I will continue our conversation from this summary.".into()],
+        ));
+        self.history.push_front(Chat::new(
+            Role::User,
+            vec![format!("Summary of our conversation so far:\n{}", summary.as_ref()).into()],
+        ));
+        if archive {
+            self.archive.extend(replaced.iter().cloned());
+        }
+        replaced
+    }
+    /// Chats replaced by summaries in `compact`, oldest first.
+    pub fn get_archive(&self) -> &[Chat] {
+        &self.archive
+    }
+    pub fn take_archive(&mut self) -> Vec<Chat> {
+        std::mem::take(&mut self.archive)
+    }
 }
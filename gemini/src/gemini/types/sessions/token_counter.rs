@@ -0,0 +1,59 @@
+use crate::gemini::types::request::{Chat, PartType};
+use std::fmt::Debug;
+
+/// Estimates tokens of a `Chat` to limit `Session` history by tokens.
+///
+/// See `Session::set_token_limit`.
+pub trait TokenCounter: Debug + Send + Sync {
+    fn count_tokens(&self, chat: &Chat) -> u32;
+}
+
+/// Offline estimate of tokens. Rough but needs no request.
+///
+/// * Text and other JSON parts: `chars_per_token` characters make a token.
+/// * Images and `FileData` of unknown size: `tokens_per_image` each.
+/// * Other inline data like PDF or audio: `bytes_per_token` decoded bytes make a token.
+#[derive(Debug, Clone)]
+pub struct HeuristicTokenCounter {
+    chars_per_token: u32,
+    tokens_per_image: u32,
+    bytes_per_token: u32,
+}
+impl HeuristicTokenCounter {
+    pub fn new(chars_per_token: u32, tokens_per_image: u32, bytes_per_token: u32) -> Self {
+        Self {
+            chars_per_token: chars_per_token.max(1),
+            tokens_per_image,
+            bytes_per_token: bytes_per_token.max(1),
+        }
+    }
+}
+impl Default for HeuristicTokenCounter {
+    /// 4 characters per token, 1032 tokens per image (4 tiles of 258 tokens) and 100 bytes per
+    /// token for other media.
+    fn default() -> Self {
+        Self::new(4, 1032, 100)
+    }
+}
+impl TokenCounter for HeuristicTokenCounter {
+    fn count_tokens(&self, chat: &Chat) -> u32 {
+        let chars_to_tokens = |chars: usize| (chars as u32).div_ceil(self.chars_per_token);
+        chat.parts()
+            .iter()
+            .map(|part| match part.data() {
+                PartType::Text(text) => chars_to_tokens(text.chars().count()),
+                PartType::InlineData(inline_data) => {
+                    if inline_data.mime_type().type_() == mime::IMAGE {
+                        self.tokens_per_image
+                    } else {
+                        // 4 base64 characters encode 3 bytes.
+                        let bytes = inline_data.data().len() as u32 / 4 * 3;
+                        bytes.div_ceil(self.bytes_per_token)
+                    }
+                }
+                PartType::FileData(_) => self.tokens_per_image,
+                data => chars_to_tokens(serde_json::to_string(data).map_or(0, |json| json.len())),
+            })
+            .sum()
+    }
+}
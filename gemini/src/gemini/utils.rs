@@ -6,10 +6,20 @@ use regex::Regex;
 use reqwest::header::HeaderMap;
 use std::time::Duration;
 mod macros;
+mod partial_json;
+mod tool_context;
+mod tool_registry;
 pub use gemini_proc_macros::{
     execute_function_calls, execute_function_calls_with_callback, gemini_function, gemini_schema,
 };
-pub use macros::GeminiSchema;
+pub use macros::{GeminiFunction, GeminiSchema};
+pub use partial_json::{
+    PartialJson, parse_partial_json, partial_json_stream, partial_json_stream_typed,
+};
+pub use tool_context::ToolContext;
+#[cfg(feature = "tokio")]
+pub use tool_registry::run_with_timeout;
+pub use tool_registry::{Approval, Approver, ToolHandler, ToolRegistry};
 
 const REQ_TIMEOUT: Duration = Duration::from_secs(10);
 
@@ -1,3 +1,5 @@
+use super::ToolContext;
+use futures::future::BoxFuture;
 use serde_json::{Value, json};
 
 /// Trait for types that can generate a Gemini-compatible JSON schema.
@@ -9,6 +11,55 @@ pub trait GeminiSchema {
     }
 }
 
+/// Function callable by Gemini, implemented by `#[gemini_function]`.
+pub trait GeminiFunction: GeminiSchema {
+    /// Name of the function in its declaration.
+    const NAME: &'static str;
+    /// If true, a `ToolRegistry` runs the function only if its approver approves the call.
+    ///
+    /// `execute_function_calls!` can't ask for approval, so it doesn't compile for such
+    /// functions:
+    /// ```compile_fail
+    /// use gemini_client_api::gemini::types::sessions::Session;
+    /// use gemini_client_api::gemini::utils::{GeminiSchema, execute_function_calls, gemini_function};
+    ///
+    /// #[gemini_function(requires_approval)]
+    /// /// Sends an email
+    /// async fn send_email(to: String) -> String {
+    ///     format!("Sent to {to}")
+    /// }
+    ///
+    /// # async fn run(mut session: Session) {
+    /// execute_function_calls!(session, send_email);
+    /// # }
+    /// ```
+    const REQUIRES_APPROVAL: bool = false;
+    /// If true, the function has `#[context]` parameters, which `execute_function_calls!`
+    /// can't pass, so it doesn't compile for such functions:
+    /// ```compile_fail
+    /// use gemini_client_api::gemini::types::sessions::Session;
+    /// use gemini_client_api::gemini::utils::{GeminiSchema, execute_function_calls, gemini_function};
+    ///
+    /// #[derive(Clone)]
+    /// struct UserId(u64);
+    ///
+    /// #[gemini_function]
+    /// /// Greets the current user
+    /// async fn greet(#[context] user: UserId) -> String {
+    ///     format!("Hi, user {}!", user.0)
+    /// }
+    ///
+    /// # async fn run(mut session: Session) {
+    /// execute_function_calls!(session, greet);
+    /// # }
+    /// ```
+    const USES_CONTEXT: bool = false;
+    /// Runs the function with `args` given by the model and `#[context]` parameters from
+    /// `context`. `Err` if `args` are invalid, a context value is missing or the function
+    /// returned `Err`.
+    fn call(args: Value, context: &ToolContext) -> BoxFuture<'static, Result<Value, String>>;
+}
+
 macro_rules! impl_primitive {
     ($ty:ty, $schema_type:expr) => {
         impl GeminiSchema for $ty {
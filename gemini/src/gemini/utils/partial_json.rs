@@ -0,0 +1,354 @@
+use futures::{Stream, StreamExt, future};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+/// Value parsed from a prefix of JSON. `complete` is false if the value was cut by the end of
+/// input.
+struct Parsed {
+    value: Option<Value>,
+    complete: bool,
+}
+impl Parsed {
+    fn complete(value: Value) -> Self {
+        Self {
+            value: Some(value),
+            complete: true,
+        }
+    }
+    fn incomplete(value: Option<Value>) -> Self {
+        Self {
+            value,
+            complete: false,
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+    fn parse_value(&mut self) -> Result<Parsed, ()> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(Parsed::incomplete(None)),
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => {
+                let (string, complete) = self.parse_string()?;
+                Ok(Parsed {
+                    value: Some(Value::String(string)),
+                    complete,
+                })
+            }
+            Some(b't') => self.parse_literal("true", Value::Bool(true)),
+            Some(b'f') => self.parse_literal("false", Value::Bool(false)),
+            Some(b'n') => self.parse_literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(()),
+        }
+    }
+    fn parse_object(&mut self) -> Result<Parsed, ()> {
+        self.position += 1;
+        let mut object = Map::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::incomplete(Some(Value::Object(object)))),
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Parsed::complete(Value::Object(object)));
+                }
+                Some(b'"') => {}
+                Some(_) => return Err(()),
+            }
+            let (key, complete) = self.parse_string()?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b':') if complete => self.position += 1,
+                None => return Ok(Parsed::incomplete(Some(Value::Object(object)))),
+                Some(_) => return Err(()),
+            }
+            let parsed = self.parse_value()?;
+            if let Some(value) = parsed.value {
+                object.insert(key, value);
+            }
+            if !parsed.complete {
+                return Ok(Parsed::incomplete(Some(Value::Object(object))));
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {}
+                None => return Ok(Parsed::incomplete(Some(Value::Object(object)))),
+                Some(_) => return Err(()),
+            }
+        }
+    }
+    fn parse_array(&mut self) -> Result<Parsed, ()> {
+        self.position += 1;
+        let mut array = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::incomplete(Some(Value::Array(array)))),
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Parsed::complete(Value::Array(array)));
+                }
+                Some(_) => {}
+            }
+            let parsed = self.parse_value()?;
+            if let Some(value) = parsed.value {
+                array.push(value);
+            }
+            if !parsed.complete {
+                return Ok(Parsed::incomplete(Some(Value::Array(array))));
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {}
+                None => return Ok(Parsed::incomplete(Some(Value::Array(array)))),
+                Some(_) => return Err(()),
+            }
+        }
+    }
+    /// Returns the string read so far and whether its closing quote was read.
+    fn parse_string(&mut self) -> Result<(String, bool), ()> {
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some(b'"') => {
+                    self.position += 1;
+                    let string = String::from_utf8(bytes).map_err(|_| ())?;
+                    return Ok((string, true));
+                }
+                Some(b'\\') => {
+                    let Some(escaped) = self.input.get(self.position + 1) else {
+                        break;
+                    };
+                    let unescaped = match escaped {
+                        b'"' => b'"',
+                        b'\\' => b'\\',
+                        b'/' => b'/',
+                        b'b' => 0x08,
+                        b'f' => 0x0c,
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        b'u' => {
+                            let Some(code) = self.unicode_escape(self.position)? else {
+                                break;
+                            };
+                            let mut length = 6;
+                            let code = if (0xD800..0xDC00).contains(&code) {
+                                // High surrogate, combined with the low surrogate after it.
+                                let Some(low) = self.unicode_escape(self.position + 6)? else {
+                                    break;
+                                };
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(());
+                                }
+                                length = 12;
+                                0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                            } else {
+                                code
+                            };
+                            let char = char::from_u32(code).ok_or(())?;
+                            bytes.extend_from_slice(char.to_string().as_bytes());
+                            self.position += length;
+                            continue;
+                        }
+                        _ => return Err(()),
+                    };
+                    bytes.push(unescaped);
+                    self.position += 2;
+                }
+                Some(byte) => {
+                    bytes.push(byte);
+                    self.position += 1;
+                }
+            }
+        }
+        let string = String::from_utf8(bytes).map_err(|_| ())?;
+        Ok((string, false))
+    }
+    /// Code of the `\uXXXX` escape at `position`. `None` if cut by the end of input.
+    fn unicode_escape(&self, position: usize) -> Result<Option<u32>, ()> {
+        let Some(escape) = self.input.get(position..position + 6) else {
+            return match self.input.get(position..) {
+                Some(rest) if b"\\u".starts_with(&rest[..rest.len().min(2)]) => Ok(None),
+                _ => Err(()),
+            };
+        };
+        if !escape.starts_with(b"\\u") {
+            return Err(());
+        }
+        let hex = std::str::from_utf8(&escape[2..]).map_err(|_| ())?;
+        u32::from_str_radix(hex, 16).map(Some).map_err(|_| ())
+    }
+    fn parse_literal(&mut self, literal: &str, value: Value) -> Result<Parsed, ()> {
+        let rest = &self.input[self.position..];
+        if rest.starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(Parsed::complete(value))
+        } else if literal.as_bytes().starts_with(rest) {
+            self.position = self.input.len();
+            Ok(Parsed::incomplete(None))
+        } else {
+            Err(())
+        }
+    }
+    fn parse_number(&mut self) -> Result<Parsed, ()> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.position += 1;
+        }
+        // A number cut by the end of input may have more digits.
+        if self.peek().is_none() {
+            return Ok(Parsed::incomplete(None));
+        }
+        let number = std::str::from_utf8(&self.input[start..self.position]).map_err(|_| ())?;
+        let number: Number = number.parse().map_err(|_| ())?;
+        Ok(Parsed::complete(Value::Number(number)))
+    }
+}
+
+/// Parses `json` which may be cut anywhere, like a reply being streamed.
+///
+/// Objects and arrays are closed where the input ends, an unterminated string keeps the
+/// characters received, and a key without value, or a number or literal that may be
+/// incomplete, is left out. Returns `None` if `json` is not the start of any JSON value.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::utils::parse_partial_json;
+/// use serde_json::json;
+/// let value = parse_partial_json(r#"{"fruits": ["apple", "ban"#).unwrap();
+/// assert_eq!(value, json!({"fruits": ["apple", "ban"]}));
+/// ```
+pub fn parse_partial_json(json: &str) -> Option<Value> {
+    let mut parser = Parser {
+        input: json.as_bytes(),
+        position: 0,
+    };
+    let parsed = parser.parse_value().ok()?;
+    if parsed.complete {
+        parser.skip_whitespace();
+        if parser.peek().is_some() {
+            return None;
+        }
+    }
+    parsed.value
+}
+
+/// Accumulates chunks of a JSON document and parses what has been received so far.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::utils::PartialJson;
+/// use serde_json::json;
+/// let mut partial = PartialJson::default();
+/// assert_eq!(partial.push(r#"{"title": "Ru"#), Some(&json!({"title": "Ru"})));
+/// assert_eq!(partial.push(r#"st"}"#), Some(&json!({"title": "Rust"})));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PartialJson {
+    text: String,
+    value: Option<Value>,
+}
+impl PartialJson {
+    /// Appends `chunk` and returns the new snapshot if it differs from the previous one.
+    pub fn push(&mut self, chunk: &str) -> Option<&Value> {
+        self.text.push_str(chunk);
+        let value = parse_partial_json(&self.text)?;
+        if self.value.as_ref() == Some(&value) {
+            return None;
+        }
+        self.value = Some(value);
+        self.value.as_ref()
+    }
+    /// Text received so far.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+    /// Latest snapshot.
+    pub fn get_value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+    /// Latest snapshot as `T`. Fields of `T` missing until later should be `Option` or
+    /// `#[serde(default)]`.
+    pub fn get_typed<T: DeserializeOwned>(&self) -> Option<T> {
+        serde_json::from_value(self.value.clone()?).ok()
+    }
+}
+
+/// Adapts a stream of text chunks of a JSON document, like `ask_as_stream` in JSON mode,
+/// to a stream of progressively filled snapshots. A snapshot is yielded only when it changes.
+///
+/// # Example
+/// ```no_run
+/// # async fn run(gemini: gemini_client_api::gemini::ask::Gemini) {
+/// use futures::StreamExt;
+/// use gemini_client_api::gemini::types::sessions::Session;
+/// use gemini_client_api::gemini::utils::partial_json_stream;
+/// let mut session = Session::new(6);
+/// session.ask("List 5 fruits as a JSON array of strings");
+/// let stream = gemini
+///     .ask_as_stream_with_extractor(session, |_, response| {
+///         response.get_chat().get_text_no_think("")
+///     })
+///     .await
+///     .map_err(|(_, error)| error)
+///     .unwrap();
+/// let mut snapshots = Box::pin(partial_json_stream(stream));
+/// while let Some(snapshot) = snapshots.next().await {
+///     println!("{}", snapshot.unwrap());
+/// }
+/// # }
+/// ```
+pub fn partial_json_stream<S, T, E>(stream: S) -> impl Stream<Item = Result<Value, E>>
+where
+    S: Stream<Item = Result<T, E>>,
+    T: AsRef<str>,
+{
+    stream
+        .scan(PartialJson::default(), |partial, chunk| {
+            let snapshot = match chunk {
+                Ok(chunk) => partial.push(chunk.as_ref()).cloned().map(Ok),
+                Err(error) => Some(Err(error)),
+            };
+            future::ready(Some(snapshot))
+        })
+        .filter_map(future::ready)
+}
+
+/// Same as `partial_json_stream` but yields snapshots deserialized to `P`, a partial version of
+/// the response type with all fields optional. Snapshots that don't fit `P` are skipped.
+pub fn partial_json_stream_typed<P, S, T, E>(stream: S) -> impl Stream<Item = Result<P, E>>
+where
+    P: DeserializeOwned,
+    S: Stream<Item = Result<T, E>>,
+    T: AsRef<str>,
+{
+    partial_json_stream(stream).filter_map(|snapshot| {
+        future::ready(match snapshot {
+            Ok(snapshot) => serde_json::from_value(snapshot).ok().map(Ok),
+            Err(error) => Some(Err(error)),
+        })
+    })
+}
@@ -0,0 +1,57 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Values given to `#[context]` parameters of `#[gemini_function]`s, keyed by type. Only one
+/// value per type is kept, so wrap values like user ids in newtypes.
+///
+/// Cloning is cheap, values are shared.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::utils::ToolContext;
+/// #[derive(Clone)]
+/// struct UserId(u64);
+/// let context = ToolContext::new().set(UserId(7));
+/// assert_eq!(context.get::<UserId>().unwrap().0, 7);
+/// ```
+#[derive(Clone, Default)]
+pub struct ToolContext {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+impl std::fmt::Debug for ToolContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolContext")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+impl ToolContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets the value of type `T`, replacing the previous one.
+    pub fn set<T: Clone + Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.insert(value);
+        self
+    }
+    /// Sets the value of type `T`, replacing the previous one.
+    pub fn insert<T: Clone + Send + Sync + 'static>(&mut self, value: T) -> &mut Self {
+        self.values.insert(TypeId::of::<T>(), Arc::new(value));
+        self
+    }
+    /// Clone of the value of type `T`.
+    pub fn get<T: Clone + 'static>(&self) -> Option<T> {
+        self.values
+            .get(&TypeId::of::<T>())?
+            .downcast_ref::<T>()
+            .cloned()
+    }
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+    /// Removes the value of type `T`. Returns false if there was none.
+    pub fn remove<T: 'static>(&mut self) -> bool {
+        self.values.remove(&TypeId::of::<T>()).is_some()
+    }
+}
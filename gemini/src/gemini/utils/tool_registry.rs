@@ -0,0 +1,378 @@
+use super::{GeminiFunction, ToolContext};
+use crate::gemini::types::request::{Chat, FunctionCall, PartType, Tool};
+use crate::gemini::types::sessions::Session;
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use futures::stream;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type ToolHandler =
+    dyn Fn(Value, &ToolContext) -> BoxFuture<'static, Result<Value, String>> + Send + Sync;
+
+#[derive(Clone)]
+struct RegisteredTool {
+    declaration: Value,
+    handler: Arc<ToolHandler>,
+    requires_approval: bool,
+    #[cfg(feature = "tokio")]
+    timeout: Option<Duration>,
+}
+
+/// Decision of the approver of a `ToolRegistry` on a function call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Approval {
+    /// Run the call as requested.
+    Approve,
+    /// Run the call with these arguments instead.
+    Edit(Value),
+    /// Don't run the call. The reason is sent to the model as the function response.
+    Reject(String),
+}
+
+pub type Approver = dyn Fn(FunctionCall) -> BoxFuture<'static, Approval> + Send + Sync;
+
+/// Runs `call` of function `name`, cancelling it after `timeout` with an error like
+/// "call of `name` timed out after 5s".
+#[cfg(feature = "tokio")]
+pub async fn run_with_timeout(
+    name: &str,
+    timeout: Duration,
+    call: impl Future<Output = Result<Value, String>>,
+) -> Result<Value, String> {
+    match tokio::time::timeout(timeout, call).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("call of `{name}` timed out after {timeout:?}")),
+    }
+}
+
+/// Functions callable by Gemini, keyed by name. Unlike `execute_function_calls!`, functions
+/// can be added and removed at runtime, like per user.
+///
+/// `#[context]` parameters of functions are taken from the context of the registry, see
+/// `set_context`. Cloning is cheap, handlers and context values are shared, so a registry can
+/// be cloned per request to set its context.
+///
+/// # Example
+/// ```
+/// use gemini_client_api::gemini::ask::Gemini;
+/// use gemini_client_api::gemini::utils::{GeminiSchema, ToolRegistry, gemini_function};
+/// use serde_json::json;
+///
+/// #[gemini_function]
+/// /// Returns the sum of two numbers.
+/// fn add(a: f64, b: f64) -> f64 {
+///     a + b
+/// }
+///
+/// let mut registry = ToolRegistry::new();
+/// registry.register::<add>().register_closure(
+///     json!({"name": "get_time", "description": "Returns the current time."}),
+///     |_args| async { Ok(json!("12:00")) },
+/// );
+/// registry.remove("get_time");
+/// let ai = Gemini::new("API_KEY", "gemini-2.5-flash", None).set_tools(vec![registry.to_tool()]);
+/// ```
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, RegisteredTool>,
+    context: ToolContext,
+    approver: Option<Arc<Approver>>,
+    max_concurrency: Option<usize>,
+    #[cfg(feature = "tokio")]
+    timeout: Option<Duration>,
+}
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ToolRegistry");
+        debug
+            .field("tools", &self.tools.keys().collect::<Vec<_>>())
+            .field("context", &self.context)
+            .field("approver", &self.approver.is_some())
+            .field("max_concurrency", &self.max_concurrency);
+        #[cfg(feature = "tokio")]
+        debug.field("timeout", &self.timeout);
+        debug.finish()
+    }
+}
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a `#[gemini_function]`, replacing a function of the same name.
+    pub fn register<F: GeminiFunction + 'static>(&mut self) -> &mut Self {
+        self.tools.insert(
+            F::NAME.to_string(),
+            RegisteredTool {
+                declaration: F::gemini_schema(),
+                handler: Arc::new(F::call),
+                requires_approval: F::REQUIRES_APPROVAL,
+                #[cfg(feature = "tokio")]
+                timeout: None,
+            },
+        );
+        self
+    }
+    /// Adds a hand written function, replacing a function of the same name.
+    /// `declaration` is a [function declaration](https://ai.google.dev/api/caching#FunctionDeclaration)
+    /// and `handler` is called with the arguments given by the model.
+    ///
+    /// # Panics
+    /// If `declaration` has no "name".
+    pub fn register_closure<F, Fut>(&mut self, declaration: Value, handler: F) -> &mut Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, String>> + Send + 'static,
+    {
+        let name = declaration["name"]
+            .as_str()
+            .expect("function declaration must have a name")
+            .to_string();
+        let handler: Arc<ToolHandler> = Arc::new(move |args, _| Box::pin(handler(args)));
+        self.tools.insert(
+            name,
+            RegisteredTool {
+                declaration,
+                handler,
+                requires_approval: false,
+                #[cfg(feature = "tokio")]
+                timeout: None,
+            },
+        );
+        self
+    }
+    /// Context given to `#[context]` parameters of the functions.
+    pub fn set_context(&mut self, context: ToolContext) -> &mut Self {
+        self.context = context;
+        self
+    }
+    pub fn get_context(&self) -> &ToolContext {
+        &self.context
+    }
+    pub fn get_context_mut(&mut self) -> &mut ToolContext {
+        &mut self.context
+    }
+    /// Marks function `name` to be run only if the approver approves the call, like
+    /// `#[gemini_function(requires_approval)]` does. Returns false if it isn't registered.
+    pub fn set_requires_approval(&mut self, name: &str, requires_approval: bool) -> bool {
+        match self.tools.get_mut(name) {
+            Some(tool) => {
+                tool.requires_approval = requires_approval;
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn requires_approval(&self, name: &str) -> bool {
+        self.tools
+            .get(name)
+            .is_some_and(|tool| tool.requires_approval)
+    }
+    /// Called with each call of functions requiring approval before running it, like to ask
+    /// a human. Without an approver, such calls are rejected.
+    ///
+    /// # Example
+    /// ```
+    /// use gemini_client_api::gemini::utils::{Approval, ToolRegistry};
+    /// let mut registry = ToolRegistry::new();
+    /// registry.set_approver(|call| async move {
+    ///     if call.name() == "send_email" {
+    ///         Approval::Reject("Emails are disabled today".into())
+    ///     } else {
+    ///         Approval::Approve
+    ///     }
+    /// });
+    /// ```
+    pub fn set_approver<F, Fut>(&mut self, approver: F) -> &mut Self
+    where
+        F: Fn(FunctionCall) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Approval> + Send + 'static,
+    {
+        self.approver = Some(Arc::new(move |call| Box::pin(approver(call))));
+        self
+    }
+    pub fn remove_approver(&mut self) -> &mut Self {
+        self.approver = None;
+        self
+    }
+    /// Maximum calls of a reply run at once. Unlimited by default.
+    pub fn set_max_concurrency(&mut self, max_concurrency: Option<usize>) -> &mut Self {
+        self.max_concurrency = max_concurrency.map(|max_concurrency| max_concurrency.max(1));
+        self
+    }
+    /// Runs the calls of a reply one by one in order, same as `set_max_concurrency(Some(1))`.
+    pub fn set_sequential(&mut self) -> &mut Self {
+        self.set_max_concurrency(Some(1))
+    }
+    pub fn get_max_concurrency(&self) -> Option<usize> {
+        self.max_concurrency
+    }
+    /// Time after which a call of any function is cancelled and gets an error as response,
+    /// unless set for the function by `set_tool_timeout`. Time spent for approval is excluded.
+    #[cfg(feature = "tokio")]
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+    /// Timeout of function `name`, overriding `set_timeout`. Returns false if it isn't
+    /// registered.
+    #[cfg(feature = "tokio")]
+    pub fn set_tool_timeout(&mut self, name: &str, timeout: Option<Duration>) -> bool {
+        match self.tools.get_mut(name) {
+            Some(tool) => {
+                tool.timeout = timeout;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Timeout applied to calls of function `name`.
+    #[cfg(feature = "tokio")]
+    pub fn get_timeout(&self, name: &str) -> Option<Duration> {
+        self.tools.get(name)?.timeout.or(self.timeout)
+    }
+    /// Removes function `name`. Returns false if it wasn't registered.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.tools.remove(name).is_some()
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+    /// Names of the registered functions, sorted.
+    pub fn get_names(&self) -> impl Iterator<Item = &str> {
+        self.tools.keys().map(String::as_str)
+    }
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+    /// Declarations of the registered functions, sorted by name.
+    pub fn get_declarations(&self) -> Vec<Value> {
+        self.tools
+            .values()
+            .map(|tool| tool.declaration.clone())
+            .collect()
+    }
+    /// `Tool::FunctionDeclarations` of the registered functions, for `Gemini::set_tools`.
+    pub fn to_tool(&self) -> Tool {
+        Tool::FunctionDeclarations(self.get_declarations())
+    }
+    /// Runs `call`, after approval if the function requires it. `Err` if the function isn't
+    /// registered, the call was rejected or the function failed.
+    pub async fn execute(&self, call: &FunctionCall) -> Result<Value, String> {
+        self.execute_bounded(call, None).await
+    }
+    /// Runs `call` like `execute`, timing out after the lower of `timeout` and the timeout of
+    /// the function, excluding the time spent for approval.
+    async fn execute_bounded(
+        &self,
+        call: &FunctionCall,
+        timeout: Option<Duration>,
+    ) -> Result<Value, String> {
+        let Some(tool) = self.tools.get(call.name()) else {
+            return Err(format!("function `{}` is not available", call.name()));
+        };
+        let mut args = call.args().clone().unwrap_or(json!({}));
+        if tool.requires_approval {
+            let Some(approver) = &self.approver else {
+                return Err(format!(
+                    "call of `{}` was rejected: approval is required but no approver is set",
+                    call.name()
+                ));
+            };
+            match approver(call.clone()).await {
+                Approval::Approve => {}
+                Approval::Edit(edited) => args = edited,
+                Approval::Reject(reason) => {
+                    return Err(format!("call of `{}` was rejected: {reason}", call.name()));
+                }
+            }
+        }
+        let result = (tool.handler)(args, &self.context);
+        #[cfg(not(feature = "tokio"))]
+        let _ = timeout;
+        #[cfg(feature = "tokio")]
+        if let Some(timeout) = [tool.timeout.or(self.timeout), timeout]
+            .into_iter()
+            .flatten()
+            .min()
+        {
+            return run_with_timeout(call.name(), timeout, result).await;
+        }
+        result.await
+    }
+    /// Runs the function calls of `chat` concurrently, upto `get_max_concurrency` at once.
+    /// Results are in the order of the calls.
+    pub async fn execute_chat(&self, chat: &Chat) -> Vec<(FunctionCall, Result<Value, String>)> {
+        let calls: Vec<FunctionCall> = chat
+            .parts()
+            .iter()
+            .filter_map(|part| match part.data() {
+                PartType::FunctionCall(call) => Some(call.clone()),
+                _ => None,
+            })
+            .collect();
+        let max_concurrency = self.max_concurrency.unwrap_or(calls.len()).max(1);
+        let results: Vec<_> = stream::iter(calls.iter().map(|call| self.execute(call)))
+            .buffered(max_concurrency)
+            .collect()
+            .await;
+        calls.into_iter().zip(results).collect()
+    }
+    /// Runs the function calls of the last chat of `session` like `execute_chat` and adds their
+    /// results to it, as `execute_function_calls!` does. `Err` results are sent as
+    /// `{"Error": error}`.
+    ///
+    /// # Returns
+    /// Calls with their results, in order. A call whose response couldn't be added has `Err`
+    /// explaining why.
+    pub async fn execute_function_calls(
+        &self,
+        session: &mut Session,
+    ) -> Vec<(FunctionCall, Result<Value, String>)> {
+        let Some(chat) = session.get_last_chat() else {
+            return Vec::new();
+        };
+        let results = self.execute_chat(chat).await;
+        results
+            .into_iter()
+            .map(|(call, result)| {
+                let response = match &result {
+                    Ok(value) => value.clone(),
+                    Err(error) => json!({"Error": error}),
+                };
+                match session.add_function_response(call.name(), response) {
+                    Ok(_) => (call, result),
+                    Err(error) => {
+                        let error = format!(
+                            "failed to add function response for `{}`: {error}",
+                            call.name()
+                        );
+                        (call, Err(error))
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl crate::gemini::ask::FunctionExecutor for ToolRegistry {
+    fn execute(&self, call: &FunctionCall) -> impl Future<Output = Result<Value, String>> + Send {
+        ToolRegistry::execute(self, call)
+    }
+    fn execute_with_timeout(
+        &self,
+        call: &FunctionCall,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Value, String>> + Send {
+        self.execute_bounded(call, Some(timeout))
+    }
+    fn get_max_concurrency(&self) -> Option<usize> {
+        self.max_concurrency
+    }
+}
@@ -0,0 +1,229 @@
+mod common;
+
+use common::{mock_gemini, text_reply};
+use gemini_client_api::gemini::ask::AgentConfig;
+use gemini_client_api::gemini::error::GeminiResponseError;
+use gemini_client_api::gemini::types::request::{
+    FunctionCall, FunctionCallingConfig, FunctionCallingMode, PartType, Role, ToolConfig,
+};
+use gemini_client_api::gemini::types::sessions::Session;
+use gemini_client_api::gemini::utils::{Approval, ToolRegistry};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+fn function_call_reply(names: &[&str]) -> Value {
+    let parts: Vec<Value> = names
+        .iter()
+        .map(|name| json!({"functionCall": {"name": name, "args": {}}}))
+        .collect();
+    json!({
+        "candidates": [{"content": {"role": "model", "parts": parts}, "finishReason": "STOP"}],
+        "modelVersion": "gemini-2.5-flash"
+    })
+}
+
+async fn echo(call: FunctionCall) -> Result<Value, String> {
+    Ok(json!(call.name()))
+}
+
+/// Function responses in the last chat of `session`.
+fn last_responses(session: &Session) -> Vec<Value> {
+    let chat = session.get_last_chat().unwrap();
+    assert_eq!(*chat.role(), Role::Function);
+    chat.parts()
+        .iter()
+        .map(|part| match part.data() {
+            PartType::FunctionResponse(response) => response.response().clone(),
+            _ => panic!("expected a function response"),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn agent_runs_calls_until_text_reply() {
+    let (gemini, requests) =
+        mock_gemini(vec![function_call_reply(&["a", "b"]), text_reply("Done")]);
+    let mut session = Session::new(10);
+    session.ask("Go");
+    let run = gemini
+        .run_agent(&mut session, &echo, AgentConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(run.iterations, 2);
+    assert_eq!(run.function_calls.len(), 2);
+    assert_eq!(run.function_calls[1].result, Ok(json!("b")));
+    assert_eq!(run.response.get_chat().get_text_all(""), "Done");
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1]["contents"][2]["role"], "function");
+}
+
+#[tokio::test]
+async fn agent_max_iterations_answers_pending_calls() {
+    let (gemini, requests) = mock_gemini(vec![function_call_reply(&["a"])]);
+    let mut session = Session::new(20);
+    session.ask("Go");
+    let error = gemini
+        .run_agent(
+            &mut session,
+            &echo,
+            AgentConfig::default().set_max_iterations(3),
+        )
+        .await
+        .unwrap_err();
+    let GeminiResponseError::MaxIterationsReached(run) = error else {
+        panic!("expected MaxIterationsReached");
+    };
+    assert_eq!(run.iterations, 3);
+    assert_eq!(requests.lock().unwrap().len(), 3);
+    assert_eq!(
+        run.function_calls[2].result,
+        Err("call of `a` was not run: maximum iterations reached".into())
+    );
+    assert_eq!(
+        last_responses(&session),
+        vec![json!({"Error": "call of `a` was not run: maximum iterations reached"})]
+    );
+}
+
+#[tokio::test]
+async fn agent_mode_none_runs_no_calls() {
+    let (gemini, requests) = mock_gemini(vec![function_call_reply(&["a"])]);
+    let gemini = gemini.set_tool_config(ToolConfig {
+        function_calling_config: Some(FunctionCallingConfig {
+            mode: Some(FunctionCallingMode::None),
+            allowed_function_names: None,
+        }),
+    });
+    let mut session = Session::new(10);
+    session.ask("Go");
+    let run = gemini
+        .run_agent(
+            &mut session,
+            &|_call: FunctionCall| async { panic!("no call must run") },
+            AgentConfig::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(run.iterations, 1);
+    assert_eq!(requests.lock().unwrap().len(), 1);
+    assert_eq!(
+        last_responses(&session),
+        vec![json!({"Error": "call of `a` was not run: function calling is disabled"})]
+    );
+}
+
+#[tokio::test]
+async fn agent_rejects_calls_not_allowed() {
+    let (gemini, requests) = mock_gemini(vec![
+        function_call_reply(&["allowed", "forbidden"]),
+        text_reply("Done"),
+    ]);
+    let gemini = gemini.set_tool_config(ToolConfig {
+        function_calling_config: Some(FunctionCallingConfig {
+            mode: Some(FunctionCallingMode::Any),
+            allowed_function_names: Some(vec!["allowed".into()]),
+        }),
+    });
+    let mut session = Session::new(10);
+    session.ask("Go");
+    let run = gemini
+        .run_agent(&mut session, &echo, AgentConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(run.function_calls[0].result, Ok(json!("allowed")));
+    assert_eq!(
+        run.function_calls[1].result,
+        Err("function `forbidden` is not allowed".into())
+    );
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0]["toolConfig"]["functionCallingConfig"]["mode"],
+        "ANY"
+    );
+    // `Any` applies to the first reply only.
+    assert_eq!(
+        requests[1]["toolConfig"]["functionCallingConfig"]["mode"],
+        "AUTO"
+    );
+}
+
+#[tokio::test]
+async fn agent_bounds_closure_calls() {
+    let (gemini, _) = mock_gemini(vec![
+        function_call_reply(&["slow", "fast"]),
+        text_reply("Done"),
+    ]);
+    let mut session = Session::new(10);
+    session.ask("Go");
+    let run = gemini
+        .run_agent(
+            &mut session,
+            &|call: FunctionCall| async move {
+                if call.name() == "slow" {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                }
+                Ok(json!(call.name()))
+            },
+            AgentConfig::default().set_timeout(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        run.function_calls[0].result,
+        Err("call of `slow` timed out after 50ms".into())
+    );
+    assert_eq!(run.function_calls[1].result, Ok(json!("fast")));
+
+    let (gemini, _) = mock_gemini(vec![
+        function_call_reply(&["a", "b", "c"]),
+        text_reply("Done"),
+    ]);
+    let running = AtomicUsize::new(0);
+    let max_running = AtomicUsize::new(0);
+    let mut session = Session::new(10);
+    session.ask("Go");
+    gemini
+        .run_agent(
+            &mut session,
+            &|_call: FunctionCall| async {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(json!(now))
+            },
+            AgentConfig::default().set_max_concurrency(1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn agent_timeout_excludes_approval() {
+    let (gemini, _) = mock_gemini(vec![function_call_reply(&["pay"]), text_reply("Done")]);
+    let mut registry = ToolRegistry::new();
+    registry
+        .register_closure(
+            json!({"name": "pay", "description": "Pays"}),
+            |_args| async { Ok(json!("paid")) },
+        )
+        .set_requires_approval("pay", true);
+    registry.set_approver(|_call| async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Approval::Approve
+    });
+    let mut session = Session::new(10);
+    session.ask("Go");
+    let run = gemini
+        .run_agent(
+            &mut session,
+            &registry,
+            AgentConfig::default().set_timeout(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(run.function_calls[0].result, Ok(json!("paid")));
+}
@@ -0,0 +1,57 @@
+// Each test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+use gemini_client_api::gemini::ask::{Endpoint, Gemini};
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// Serves `replies` in order to `generateContent` requests, repeating the last one.
+/// Returns the client and the bodies of the requests received.
+pub fn mock_gemini(replies: Vec<Value>) -> (Gemini, Arc<Mutex<Vec<Value>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let mut received = received.lock().unwrap();
+            received.push(serde_json::from_slice::<Value>(&body).unwrap());
+            let reply = replies[(received.len() - 1).min(replies.len() - 1)].to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                reply.len()
+            )
+            .unwrap();
+        }
+    });
+    let gemini = Gemini::new("API_KEY", "gemini-2.5-flash", None)
+        .set_endpoint(Endpoint::new(format!("http://{address}")));
+    (gemini, requests)
+}
+
+pub fn text_reply(text: &str) -> Value {
+    json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": text}]}, "finishReason": "STOP"}],
+        "modelVersion": "gemini-2.5-flash"
+    })
+}
@@ -1,8 +1,13 @@
-use gemini_client_api::gemini::ask::Gemini;
-use gemini_client_api::gemini::types::request::{FunctionCall, PartType, Role, Tool};
+use gemini_client_api::gemini::ask::{AgentConfig, Gemini};
+use gemini_client_api::gemini::types::request::{
+    Chat, FunctionCall, FunctionCallingConfig, FunctionCallingMode, PartType, Role, Tool,
+    ToolConfig,
+};
 use gemini_client_api::gemini::{
     types::sessions::Session,
-    utils::{GeminiSchema, execute_function_calls, gemini_function},
+    utils::{
+        Approval, GeminiSchema, ToolContext, ToolRegistry, execute_function_calls, gemini_function,
+    },
 };
 use serde_json::json;
 use std::error::Error;
@@ -136,3 +141,316 @@ async fn ask_with_function_calls() {
         println!("{:?}", response.get_chat().parts());
     }
 }
+
+#[tokio::test]
+async fn ask_with_agent() {
+    let mut session = Session::new(20);
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash",
+        None,
+    )
+    .set_tools(vec![Tool::FunctionDeclarations(vec![
+        add_numbers::gemini_schema(),
+        greet::gemini_schema(),
+    ])])
+    .set_tool_config(ToolConfig {
+        function_calling_config: Some(FunctionCallingConfig {
+            mode: Some(FunctionCallingMode::Any),
+            allowed_function_names: Some(vec!["add_numbers".into()]),
+        }),
+    });
+    session.ask("What is 12 + 30? Use the tools.");
+    let run = ai
+        .run_agent(
+            &mut session,
+            &|call: FunctionCall| async move {
+                let args = call.args().clone().unwrap_or(json!({}));
+                match call.name().as_str() {
+                    "add_numbers" => add_numbers::execute(&args).await,
+                    "greet" => greet::execute(&args).await,
+                    name => Err(format!("unknown function {name}")),
+                }
+            },
+            AgentConfig::default().set_max_iterations(4),
+        )
+        .await
+        .unwrap();
+    assert!(run.iterations >= 2);
+    assert_eq!(run.function_calls[0].call.name(), "add_numbers");
+    assert_eq!(run.function_calls[0].result, Ok(json!(42)));
+    assert!(run.response.get_chat().get_text_no_think("").contains("42"));
+}
+
+#[tokio::test]
+async fn tool_registry_test() {
+    let mut registry = ToolRegistry::new();
+    registry
+        .register::<add_numbers>()
+        .register::<greet>()
+        .register::<fail_fn>()
+        .register_closure(
+            json!({"name": "get_time", "description": "Returns the current time"}),
+            |_args| async { Ok(json!("12:00")) },
+        );
+    assert!(registry.remove("greet"));
+    assert!(!registry.remove("greet"));
+    assert_eq!(
+        registry.get_names().collect::<Vec<_>>(),
+        vec!["add_numbers", "fail_fn", "get_time"]
+    );
+    assert_eq!(registry.get_declarations()[0], add_numbers::gemini_schema());
+
+    let mut session = Session::new(10);
+    session.reply_parts(vec![
+        FunctionCall::new("add_numbers".to_string(), Some(json!({"a": 1, "b": 2}))).into(),
+        FunctionCall::new("get_time".to_string(), None).into(),
+        FunctionCall::new("greet".to_string(), Some(json!({"name": "Gemini"}))).into(),
+        FunctionCall::new("fail_fn".to_string(), None).into(),
+    ]);
+    let results = registry.execute_function_calls(&mut session).await;
+    let results: Vec<_> = results.into_iter().map(|(_, result)| result).collect();
+    assert_eq!(
+        results,
+        vec![
+            Ok(json!(3)),
+            Ok(json!("12:00")),
+            Err("function `greet` is not available".into()),
+            Err("Simulated failure".into()),
+        ]
+    );
+    let history = session.get_history();
+    assert_eq!(*history[1].role(), Role::Function);
+    assert_eq!(history[1].parts().len(), 4);
+}
+
+#[derive(Clone)]
+struct UserId(u32);
+
+#[gemini_function]
+/// Greets the current user
+async fn greet_user(
+    #[context] user: UserId,
+    /// Greeting to use
+    greeting: String,
+) -> String {
+    format!("{greeting}, user {}!", user.0)
+}
+
+#[tokio::test]
+async fn context_parameters() {
+    let schema = greet_user::gemini_schema();
+    assert_eq!(schema["parameters"]["required"], json!(["greeting"]));
+    assert!(schema["parameters"]["properties"].get("user").is_none());
+
+    let args = json!({"greeting": "Hi"});
+    let context = ToolContext::new().set(UserId(7));
+    assert_eq!(
+        greet_user::execute_with_context(&args, &context).await,
+        Ok(json!("Hi, user 7!"))
+    );
+    assert!(greet_user::execute(&args).await.is_err());
+
+    let mut registry = ToolRegistry::new();
+    registry.register::<greet_user>();
+    let call = FunctionCall::new("greet_user".to_string(), Some(args));
+    assert!(registry.execute(&call).await.is_err());
+    registry.get_context_mut().insert(UserId(8));
+    assert_eq!(registry.execute(&call).await, Ok(json!("Hi, user 8!")));
+}
+
+#[gemini_function(requires_approval)]
+/// Sends money to a person
+async fn send_money(to: String, amount: i32) -> String {
+    format!("Sent {amount} to {to}")
+}
+
+#[tokio::test]
+async fn approval_gate() {
+    let mut registry = ToolRegistry::new();
+    registry.register::<send_money>().register::<add_numbers>();
+    assert!(registry.requires_approval("send_money"));
+    assert!(!registry.requires_approval("add_numbers"));
+
+    let send = FunctionCall::new(
+        "send_money".to_string(),
+        Some(json!({"to": "Bob", "amount": 500})),
+    );
+    let add = FunctionCall::new("add_numbers".to_string(), Some(json!({"a": 1, "b": 2})));
+    // Without an approver, calls needing approval are rejected.
+    assert!(registry.execute(&send).await.is_err());
+
+    registry.set_approver(|call| async move {
+        match call.args().as_ref().unwrap()["amount"].as_i64() {
+            Some(amount) if amount > 1000 => Approval::Reject("Too much".into()),
+            Some(amount) if amount > 100 => {
+                Approval::Edit(json!({"to": call.args().as_ref().unwrap()["to"], "amount": 100}))
+            }
+            _ => Approval::Approve,
+        }
+    });
+    assert_eq!(registry.execute(&send).await, Ok(json!("Sent 100 to Bob")));
+    assert_eq!(registry.execute(&add).await, Ok(json!(3)));
+
+    let mut session = Session::new(10);
+    session.reply_parts(vec![
+        FunctionCall::new(
+            "send_money".to_string(),
+            Some(json!({"to": "Eve", "amount": 5000})),
+        )
+        .into(),
+    ]);
+    let results = registry.execute_function_calls(&mut session).await;
+    assert_eq!(
+        results[0].1,
+        Err("call of `send_money` was rejected: Too much".into())
+    );
+    let PartType::FunctionResponse(response) = session.get_last_chat().unwrap().parts()[0].data()
+    else {
+        panic!("expected a function response");
+    };
+    assert_eq!(
+        response.response(),
+        &json!({"Error": "call of `send_money` was rejected: Too much"})
+    );
+
+    assert!(registry.set_requires_approval("send_money", false));
+    registry.remove_approver();
+    assert_eq!(registry.execute(&send).await, Ok(json!("Sent 500 to Bob")));
+}
+
+#[gemini_function]
+/// Waits for some milliseconds
+async fn wait(millis: u64) -> u64 {
+    tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+    millis
+}
+
+#[tokio::test]
+async fn timeouts_and_concurrency() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let mut registry = ToolRegistry::new();
+    registry.register::<wait>().register_closure(
+        json!({"name": "count", "description": "Counts running calls"}),
+        {
+            let (running, max_running) = (running.clone(), max_running.clone());
+            move |_args| {
+                let (running, max_running) = (running.clone(), max_running.clone());
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(json!(now))
+                }
+            }
+        },
+    );
+    registry.set_timeout(Some(Duration::from_millis(100)));
+    assert!(registry.set_tool_timeout("wait", Some(Duration::from_millis(50))));
+    assert!(!registry.set_tool_timeout("missing", None));
+    assert_eq!(
+        registry.get_timeout("wait"),
+        Some(Duration::from_millis(50))
+    );
+    assert_eq!(
+        registry.get_timeout("count"),
+        Some(Duration::from_millis(100))
+    );
+
+    let mut session = Session::new(10);
+    session.reply_parts(vec![
+        FunctionCall::new("wait".to_string(), Some(json!({"millis": 10_000}))).into(),
+        FunctionCall::new("wait".to_string(), Some(json!({"millis": 1}))).into(),
+    ]);
+    let results = registry.execute_function_calls(&mut session).await;
+    assert_eq!(
+        results[0].1,
+        Err("call of `wait` timed out after 50ms".into())
+    );
+    assert_eq!(results[1].1, Ok(json!(1)));
+    let PartType::FunctionResponse(response) = session.get_last_chat().unwrap().parts()[0].data()
+    else {
+        panic!("expected a function response");
+    };
+    assert_eq!(
+        response.response(),
+        &json!({"Error": "call of `wait` timed out after 50ms"})
+    );
+
+    let calls = Chat::new(
+        Role::Model,
+        (0..6)
+            .map(|_| FunctionCall::new("count".to_string(), None).into())
+            .collect(),
+    );
+    registry.set_max_concurrency(Some(2));
+    assert_eq!(registry.execute_chat(&calls).await.len(), 6);
+    assert_eq!(max_running.swap(0, Ordering::SeqCst), 2);
+    registry.set_sequential();
+    assert_eq!(registry.get_max_concurrency(), Some(1));
+    registry.execute_chat(&calls).await;
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+}
+
+static RUNNING: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+static MAX_RUNNING: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+async fn count_running() -> usize {
+    use std::sync::atomic::Ordering;
+    let now = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    MAX_RUNNING.fetch_max(now, Ordering::SeqCst);
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+    now
+}
+
+#[gemini_function]
+/// Counts running calls
+async fn first_count() -> usize {
+    count_running().await
+}
+
+#[gemini_function]
+/// Counts running calls too
+async fn second_count() -> usize {
+    count_running().await
+}
+
+#[tokio::test]
+async fn execute_macro_bounds() {
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    let mut session = Session::new(10);
+    session.reply_parts(vec![
+        FunctionCall::new("wait".to_string(), Some(json!({"millis": 10_000}))).into(),
+        FunctionCall::new("add_numbers".to_string(), Some(json!({"a": 1, "b": 2}))).into(),
+    ]);
+    let results = execute_function_calls!(
+        session,
+        timeout = Duration::from_millis(50),
+        wait,
+        add_numbers
+    );
+    assert_eq!(
+        results,
+        vec![
+            Some(Err("call of `wait` timed out after 50ms".into())),
+            Some(Ok(json!(3)))
+        ]
+    );
+
+    session.reply_parts(vec![
+        FunctionCall::new("first_count".to_string(), None).into(),
+        FunctionCall::new("second_count".to_string(), None).into(),
+    ]);
+    let results = execute_function_calls!(session, max_concurrency = 1, first_count, second_count);
+    assert_eq!(results, vec![Some(Ok(json!(1))), Some(Ok(json!(1)))]);
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 1);
+}
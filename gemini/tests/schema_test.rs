@@ -1,4 +1,7 @@
-use gemini_client_api::gemini::types::request::Tool;
+mod common;
+
+use common::{mock_gemini, text_reply};
+use gemini_client_api::gemini::types::request::{Role, Tool};
 use gemini_client_api::gemini::{
     ask::Gemini,
     types::sessions::Session,
@@ -131,6 +134,60 @@ async fn ask_string_for_json_with_struct() {
     )
 }
 
+#[tokio::test]
+async fn ask_structured_with_struct() {
+    #[derive(Debug, Deserialize)]
+    #[gemini_schema]
+    struct Capital {
+        country: String,
+        city: String,
+    }
+    let ai = Gemini::new(
+        std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY not found"),
+        "gemini-2.5-flash",
+        None,
+    );
+    let mut session = Session::new(6);
+    session.ask("What is the capital of France?");
+    let (capital, _) = ai
+        .ask_structured_with_reprompt::<Capital>(&mut session, 3)
+        .await
+        .unwrap();
+    assert_eq!(capital.country, "France");
+    assert_eq!(capital.city, "Paris");
+    assert_eq!(session.get_history_length(), 2);
+    assert!(ai.get_generation_config().is_none());
+}
+
+#[tokio::test]
+async fn reprompt_keeps_prompt_within_history_limit() {
+    #[derive(Debug, Deserialize)]
+    #[gemini_schema]
+    struct Capital {
+        city: String,
+    }
+    let (ai, requests) = mock_gemini(vec![
+        text_reply("Paris"),
+        text_reply(r#"{"city": "Paris"}"#),
+    ]);
+    let mut session = Session::new(2);
+    session.ask("What is the capital of France?");
+    let (capital, _) = ai
+        .ask_structured_with_reprompt::<Capital>(&mut session, 2)
+        .await
+        .unwrap();
+    assert_eq!(capital.city, "Paris");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    let history = session.get_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(*history[0].role(), Role::User);
+    assert_eq!(
+        history[0].get_text_all(""),
+        "What is the capital of France?"
+    );
+    assert_eq!(history[1].get_text_all(""), r#"{"city": "Paris"}"#);
+}
+
 #[allow(dead_code)]
 #[gemini_function]
 /// Get the current weather in a given location
@@ -0,0 +1,32 @@
+mod common;
+
+use common::mock_gemini;
+use gemini_client_api::gemini::ask::{ApiTokenCounter, RateLimiter};
+use gemini_client_api::gemini::types::sessions::{Session, TokenCounter};
+use serde_json::json;
+
+#[tokio::test]
+async fn api_token_counter_measures_each_chat_once() {
+    let (gemini, requests) = mock_gemini(vec![json!({"totalTokens": 7})]);
+    let gemini = gemini.set_rate_limiter(Some(RateLimiter::new(Some(100), None, Some(1))));
+    let counter = ApiTokenCounter::new(gemini).set_capacity(2);
+    let mut session = Session::new(10);
+    session.ask("Hi");
+    session.reply("Hello");
+    session.ask("Hi");
+    counter.measure(&session).await.unwrap();
+    // The repeated prompt is measured once.
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    assert_eq!(counter.len(), 2);
+    let history = session.get_history();
+    assert_eq!(counter.count_tokens(history[1]), 7);
+
+    counter.measure(&session).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    session.reply("Bye");
+    counter.measure(&session).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 3);
+    // The oldest count is forgotten past the capacity.
+    assert_eq!(counter.len(), 2);
+}